@@ -1,68 +1,479 @@
+pub mod canonical;
+pub mod compat;
+pub mod idl;
+pub mod validate;
+
+pub use canonical::{canonical_form, Fingerprints};
+pub use compat::{check_compatibility, Compatibility, Incompatibility};
+pub use idl::to_idl;
+pub use validate::{validate, validate_documents};
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Non-standard attributes carried on an Avro type or field (e.g. `"format"`),
+/// in the order they were written.
+pub type Attributes = Map<String, Value>;
+
+/// The Avro primitive type names.
+pub const PRIMITIVE_TYPES: &[&str] = &[
+    "null", "boolean", "int", "long", "float", "double", "bytes", "string",
+];
 
 /// Represents the different forms of Avro types that can be generated
 /// from JSON Schema input.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(untagged)]
+///
+/// Serializes to (and deserializes from) the Avro JSON schema notation, so a
+/// converted schema can be written as `.avsc` or walked and transformed in Rust.
+/// A list of top-level types is a [`AvroType::Union`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
 pub enum AvroType {
     /// A primitive Avro type, e.g. `"string"`, `"int"`, `"boolean"`.
-    Primitive(String),
+    Primitive {
+        r#type: String,
+        /// Custom attributes; when present the type is written as an object.
+        attributes: Attributes,
+    },
     /// An Avro record type with named fields.
     Record {
-        #[serde(rename = "type")]
-        r#type: String,
         name: String,
         /// Optional namespace for the record.
-        #[serde(skip_serializing_if = "Option::is_none")]
         namespace: Option<String>,
-        /// List of record fields.
-        fields: Vec<AvroField>,
+        /// Alternative names the record is also known by.
+        aliases: Vec<String>,
         /// Optional documentation string.
-        #[serde(skip_serializing_if = "Option::is_none")]
         doc: Option<String>,
-        /// Dependencies on other Avro types.
-        #[serde(skip_serializing_if = "Option::is_none")]
-        dependencies: Option<Vec<String>>,
+        /// List of record fields.
+        fields: Vec<AvroField>,
+        attributes: Attributes,
     },
     /// An Avro enum type with symbols.
     Enum {
-        #[serde(rename = "type")]
-        r#type: String,
         /// Enum name.
         name: String,
         /// Optional namespace for the enum.
-        #[serde(skip_serializing_if = "Option::is_none")]
         namespace: Option<String>,
+        /// Alternative names the enum is also known by.
+        aliases: Vec<String>,
+        /// Optional documentation string.
+        doc: Option<String>,
         /// Allowed symbols in the enum.
         symbols: Vec<String>,
+        /// Symbol used by readers for symbols they don't know.
+        default: Option<String>,
+        attributes: Attributes,
+    },
+    /// An Avro fixed type of `size` bytes.
+    Fixed {
+        name: String,
+        /// Optional namespace for the fixed type.
+        namespace: Option<String>,
+        /// Alternative names the fixed type is also known by.
+        aliases: Vec<String>,
+        /// Number of bytes per value.
+        size: u64,
+        attributes: Attributes,
     },
     /// An Avro array type.
     Array {
-        #[serde(rename = "type")]
-        r#type: String,
         /// Item type contained in the array.
         items: Box<AvroType>,
+        attributes: Attributes,
     },
     /// An Avro map type with string keys.
     Map {
-        #[serde(rename = "type")]
-        r#type: String,
         /// Value type for map entries.
         values: Box<AvroType>,
+        attributes: Attributes,
+    },
+    /// A logical type annotating a primitive or fixed type, e.g. `date` on
+    /// `int` or `decimal` on `bytes`.
+    Logical {
+        logical_type: String,
+        /// The underlying primitive or fixed type.
+        base: Box<AvroType>,
+        /// Parameters of the logical type (e.g. `precision`, `scale`).
+        attributes: Attributes,
     },
     /// A union of multiple Avro types.
     Union(Vec<AvroType>),
+    /// A reference by (full) name to a named type defined elsewhere.
+    Reference(String),
 }
 
 /// A field inside an Avro record.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
 pub struct AvroField {
     /// Name of the field.
     pub name: String,
     /// Avro type of the field.
-    #[serde(rename = "type")]
     pub field_type: AvroType,
     /// Optional documentation string.
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub doc: Option<String>,
+    /// Default value; `Some(Value::Null)` is an explicit `null` default.
+    pub default: Option<Value>,
+    /// Alternative names the field is also known by.
+    pub aliases: Vec<String>,
+    /// Custom attributes (e.g. `"const"`).
+    pub attributes: Attributes,
+}
+
+impl AvroType {
+    /// A primitive type without attributes.
+    pub fn primitive(name: &str) -> Self {
+        AvroType::Primitive {
+            r#type: name.to_string(),
+            attributes: Attributes::new(),
+        }
+    }
+
+    /// Name of a record, enum or fixed type.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            AvroType::Record { name, .. }
+            | AvroType::Enum { name, .. }
+            | AvroType::Fixed { name, .. } => Some(name),
+            AvroType::Logical { base, .. } => base.name(),
+            _ => None,
+        }
+    }
+
+    /// Full name (`namespace.name`) of a record, enum or fixed type.
+    pub fn full_name(&self) -> Option<String> {
+        match self {
+            AvroType::Record {
+                name, namespace, ..
+            }
+            | AvroType::Enum {
+                name, namespace, ..
+            }
+            | AvroType::Fixed {
+                name, namespace, ..
+            } => Some(match namespace.as_deref() {
+                Some(ns) if !ns.is_empty() => format!("{ns}.{name}"),
+                _ => name.clone(),
+            }),
+            AvroType::Logical { base, .. } => base.full_name(),
+            _ => None,
+        }
+    }
+}
+
+impl AvroField {
+    /// The field's name for `purpose` (e.g. `"json"`, the property it was
+    /// converted from) from its `altnames` attribute, or its name.
+    pub fn altname(&self, purpose: &str) -> &str {
+        self.attributes
+            .get("altnames")
+            .and_then(|altnames| altnames.get(purpose))
+            .and_then(Value::as_str)
+            .unwrap_or(&self.name)
+    }
+}
+
+impl AvroType {
+    /// The value an enum symbol stands for under `purpose`, from the enum's
+    /// `altsymbols` attribute, if it was renamed.
+    pub fn altsymbol(&self, purpose: &str, symbol: &str) -> Option<&Value> {
+        match self {
+            AvroType::Enum { attributes, .. } => {
+                attributes.get("altsymbols")?.get(purpose)?.get(symbol)
+            }
+            AvroType::Logical { base, .. } => base.altsymbol(purpose, symbol),
+            _ => None,
+        }
+    }
+}
+
+fn take_string(obj: &mut Map<String, Value>, key: &str) -> Result<Option<String>, String> {
+    match obj.shift_remove(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(other) => Err(format!("\"{key}\" must be a string, got {other}")),
+    }
+}
+
+fn take_strings(obj: &mut Map<String, Value>, key: &str) -> Result<Vec<String>, String> {
+    match obj.shift_remove(key) {
+        None => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|v| match v {
+                Value::String(s) => Ok(s),
+                other => Err(format!("\"{key}\" entries must be strings, got {other}")),
+            })
+            .collect(),
+        Some(other) => Err(format!("\"{key}\" must be an array, got {other}")),
+    }
+}
+
+fn require<T>(value: Option<T>, key: &str, kind: &str) -> Result<T, String> {
+    value.ok_or_else(|| format!("{kind} is missing \"{key}\""))
+}
+
+fn insert_named(
+    obj: &mut Map<String, Value>,
+    kind: &str,
+    name: String,
+    namespace: Option<String>,
+    aliases: Vec<String>,
+) {
+    obj.insert("type".to_string(), Value::String(kind.to_string()));
+    obj.insert("name".to_string(), Value::String(name));
+    if let Some(ns) = namespace {
+        obj.insert("namespace".to_string(), Value::String(ns));
+    }
+    if !aliases.is_empty() {
+        obj.insert("aliases".to_string(), aliases.into());
+    }
+}
+
+impl TryFrom<Value> for AvroType {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut obj = match value {
+            Value::String(name) if PRIMITIVE_TYPES.contains(&name.as_str()) => {
+                return Ok(AvroType::primitive(&name));
+            }
+            Value::String(name) => return Ok(AvroType::Reference(name)),
+            Value::Array(items) => {
+                return items
+                    .into_iter()
+                    .map(AvroType::try_from)
+                    .collect::<Result<_, _>>()
+                    .map(AvroType::Union);
+            }
+            Value::Object(obj) => obj,
+            other => return Err(format!("not an Avro type: {other}")),
+        };
+
+        if let Some(Value::String(logical_type)) = obj.shift_remove("logicalType") {
+            let mut base = AvroType::try_from(Value::Object(obj))?;
+            // Whatever the base type doesn't define belongs to the logical type
+            let attributes = match &mut base {
+                AvroType::Primitive { attributes, .. } | AvroType::Fixed { attributes, .. } => {
+                    std::mem::take(attributes)
+                }
+                _ => Attributes::new(),
+            };
+            return Ok(AvroType::Logical {
+                logical_type,
+                base: Box::new(base),
+                attributes,
+            });
+        }
+
+        let kind = match obj.shift_remove("type") {
+            Some(Value::String(kind)) => kind,
+            Some(nested) => {
+                // `{"type": {...}}` wraps another type; fold attributes into it
+                let mut inner = AvroType::try_from(nested)?;
+                if !obj.is_empty() {
+                    match &mut inner {
+                        AvroType::Primitive { attributes, .. }
+                        | AvroType::Record { attributes, .. }
+                        | AvroType::Enum { attributes, .. }
+                        | AvroType::Fixed { attributes, .. }
+                        | AvroType::Array { attributes, .. }
+                        | AvroType::Map { attributes, .. }
+                        | AvroType::Logical { attributes, .. } => attributes.extend(obj),
+                        _ => return Err("attributes on a union or reference".to_string()),
+                    }
+                }
+                return Ok(inner);
+            }
+            None => return Err("object is missing \"type\"".to_string()),
+        };
+
+        Ok(match kind.as_str() {
+            "record" | "error" => AvroType::Record {
+                name: require(take_string(&mut obj, "name")?, "name", "record")?,
+                namespace: take_string(&mut obj, "namespace")?,
+                aliases: take_strings(&mut obj, "aliases")?,
+                doc: take_string(&mut obj, "doc")?,
+                fields: match obj.shift_remove("fields") {
+                    Some(Value::Array(fields)) => fields
+                        .into_iter()
+                        .map(AvroField::try_from)
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err("record is missing \"fields\"".to_string()),
+                },
+                attributes: obj,
+            },
+            "enum" => AvroType::Enum {
+                name: require(take_string(&mut obj, "name")?, "name", "enum")?,
+                namespace: take_string(&mut obj, "namespace")?,
+                aliases: take_strings(&mut obj, "aliases")?,
+                doc: take_string(&mut obj, "doc")?,
+                symbols: take_strings(&mut obj, "symbols")?,
+                default: take_string(&mut obj, "default")?,
+                attributes: obj,
+            },
+            "fixed" => AvroType::Fixed {
+                name: require(take_string(&mut obj, "name")?, "name", "fixed")?,
+                namespace: take_string(&mut obj, "namespace")?,
+                aliases: take_strings(&mut obj, "aliases")?,
+                size: require(
+                    obj.shift_remove("size").and_then(|s| s.as_u64()),
+                    "size",
+                    "fixed",
+                )?,
+                attributes: obj,
+            },
+            "array" => AvroType::Array {
+                items: Box::new(AvroType::try_from(require(
+                    obj.shift_remove("items"),
+                    "items",
+                    "array",
+                )?)?),
+                attributes: obj,
+            },
+            "map" => AvroType::Map {
+                values: Box::new(AvroType::try_from(require(
+                    obj.shift_remove("values"),
+                    "values",
+                    "map",
+                )?)?),
+                attributes: obj,
+            },
+            primitive if PRIMITIVE_TYPES.contains(&primitive) => AvroType::Primitive {
+                r#type: kind,
+                attributes: obj,
+            },
+            _ if obj.is_empty() => AvroType::Reference(kind),
+            _ => return Err(format!("attributes on a reference to {kind}")),
+        })
+    }
+}
+
+impl From<AvroType> for Value {
+    fn from(avro_type: AvroType) -> Self {
+        let mut obj = Map::new();
+        match avro_type {
+            AvroType::Primitive { r#type, attributes } if attributes.is_empty() => {
+                return Value::String(r#type);
+            }
+            AvroType::Primitive { r#type, attributes } => {
+                obj.insert("type".to_string(), Value::String(r#type));
+                obj.extend(attributes);
+            }
+            AvroType::Record {
+                name,
+                namespace,
+                aliases,
+                doc,
+                fields,
+                attributes,
+            } => {
+                insert_named(&mut obj, "record", name, namespace, aliases);
+                obj.insert(
+                    "fields".to_string(),
+                    Value::Array(fields.into_iter().map(Value::from).collect()),
+                );
+                if let Some(doc) = doc {
+                    obj.insert("doc".to_string(), Value::String(doc));
+                }
+                obj.extend(attributes);
+            }
+            AvroType::Enum {
+                name,
+                namespace,
+                aliases,
+                doc,
+                symbols,
+                default,
+                attributes,
+            } => {
+                insert_named(&mut obj, "enum", name, namespace, aliases);
+                obj.insert("symbols".to_string(), symbols.into());
+                if let Some(default) = default {
+                    obj.insert("default".to_string(), Value::String(default));
+                }
+                if let Some(doc) = doc {
+                    obj.insert("doc".to_string(), Value::String(doc));
+                }
+                obj.extend(attributes);
+            }
+            AvroType::Fixed {
+                name,
+                namespace,
+                aliases,
+                size,
+                attributes,
+            } => {
+                insert_named(&mut obj, "fixed", name, namespace, aliases);
+                obj.insert("size".to_string(), size.into());
+                obj.extend(attributes);
+            }
+            AvroType::Array { items, attributes } => {
+                obj.insert("type".to_string(), "array".into());
+                obj.insert("items".to_string(), Value::from(*items));
+                obj.extend(attributes);
+            }
+            AvroType::Map { values, attributes } => {
+                obj.insert("type".to_string(), "map".into());
+                obj.insert("values".to_string(), Value::from(*values));
+                obj.extend(attributes);
+            }
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => {
+                match Value::from(*base) {
+                    Value::Object(base) => obj.extend(base),
+                    base => {
+                        obj.insert("type".to_string(), base);
+                    }
+                }
+                obj.insert("logicalType".to_string(), Value::String(logical_type));
+                obj.extend(attributes);
+            }
+            AvroType::Union(types) => {
+                return Value::Array(types.into_iter().map(Value::from).collect());
+            }
+            AvroType::Reference(name) => return Value::String(name),
+        }
+        Value::Object(obj)
+    }
+}
+
+impl TryFrom<Value> for AvroField {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let Value::Object(mut obj) = value else {
+            return Err(format!("not an Avro field: {value}"));
+        };
+        Ok(AvroField {
+            name: require(take_string(&mut obj, "name")?, "name", "field")?,
+            field_type: AvroType::try_from(require(obj.shift_remove("type"), "type", "field")?)?,
+            doc: take_string(&mut obj, "doc")?,
+            default: obj.shift_remove("default"),
+            aliases: take_strings(&mut obj, "aliases")?,
+            attributes: obj,
+        })
+    }
+}
+
+impl From<AvroField> for Value {
+    fn from(field: AvroField) -> Self {
+        let mut obj = Map::new();
+        obj.insert("name".to_string(), Value::String(field.name));
+        obj.insert("type".to_string(), Value::from(field.field_type));
+        if let Some(default) = field.default {
+            obj.insert("default".to_string(), default);
+        }
+        if !field.aliases.is_empty() {
+            obj.insert("aliases".to_string(), field.aliases.into());
+        }
+        obj.extend(field.attributes);
+        if let Some(doc) = field.doc {
+            obj.insert("doc".to_string(), Value::String(doc));
+        }
+        Value::Object(obj)
+    }
 }
@@ -0,0 +1,194 @@
+//! Avro Parsing Canonical Form and schema fingerprints.
+//!
+//! Two schemas with the same canonical form read and write the same data, so
+//! its fingerprints identify a schema across Avro implementations (e.g. as
+//! schema registry or cache keys).
+use md5::Md5;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use super::AvroType;
+
+/// The Parsing Canonical Form of `schema`, as compact JSON.
+///
+/// Names are replaced by full names, attributes other than `type`, `name`,
+/// `fields`, `symbols`, `items`, `values` and `size` are dropped (including
+/// `doc`, `aliases`, defaults and logical types), and keys are written in
+/// that order without whitespace.
+pub fn canonical_form(schema: &AvroType) -> String {
+    serde_json::to_string(&Canonicalizer::default().canonical(schema, "")).unwrap()
+}
+
+#[derive(Default)]
+struct Canonicalizer {
+    /// Full names of the named types seen so far.
+    defined: HashSet<String>,
+}
+
+impl Canonicalizer {
+    fn full_name(&self, name: &str, namespace: &str) -> String {
+        if name.contains('.') || namespace.is_empty() {
+            return name.to_string();
+        }
+        let qualified = format!("{namespace}.{name}");
+        if self.defined.contains(name) && !self.defined.contains(&qualified) {
+            name.to_string()
+        } else {
+            qualified
+        }
+    }
+
+    /// Start the object of a named type, returning it with the namespace its
+    /// children are written in.
+    fn named(
+        &mut self,
+        kind: &str,
+        name: &str,
+        namespace: &Option<String>,
+        parent_namespace: &str,
+    ) -> (Map<String, Value>, String) {
+        let (ns, short) = match name.rsplit_once('.') {
+            Some((ns, short)) => (ns, short),
+            None => (namespace.as_deref().unwrap_or(parent_namespace), name),
+        };
+        let full_name = if ns.is_empty() {
+            short.to_string()
+        } else {
+            format!("{ns}.{short}")
+        };
+        self.defined.insert(full_name.clone());
+        let mut obj = Map::new();
+        obj.insert("name".to_string(), Value::String(full_name));
+        obj.insert("type".to_string(), Value::String(kind.to_string()));
+        (obj, ns.to_string())
+    }
+
+    fn canonical(&mut self, avro_type: &AvroType, namespace: &str) -> Value {
+        match avro_type {
+            AvroType::Primitive { r#type, .. } => Value::String(r#type.clone()),
+            AvroType::Reference(name) => Value::String(self.full_name(name, namespace)),
+            AvroType::Logical { base, .. } => self.canonical(base, namespace),
+            AvroType::Record {
+                name,
+                namespace: ns,
+                fields,
+                ..
+            } => {
+                let (mut obj, ns) = self.named("record", name, ns, namespace);
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let mut obj = Map::new();
+                        obj.insert("name".to_string(), Value::String(field.name.clone()));
+                        obj.insert("type".to_string(), self.canonical(&field.field_type, &ns));
+                        Value::Object(obj)
+                    })
+                    .collect();
+                obj.insert("fields".to_string(), Value::Array(fields));
+                Value::Object(obj)
+            }
+            AvroType::Enum {
+                name,
+                namespace: ns,
+                symbols,
+                ..
+            } => {
+                let (mut obj, _) = self.named("enum", name, ns, namespace);
+                obj.insert("symbols".to_string(), symbols.clone().into());
+                Value::Object(obj)
+            }
+            AvroType::Fixed {
+                name,
+                namespace: ns,
+                size,
+                ..
+            } => {
+                let (mut obj, _) = self.named("fixed", name, ns, namespace);
+                obj.insert("size".to_string(), (*size).into());
+                Value::Object(obj)
+            }
+            AvroType::Array { items, .. } => {
+                let mut obj = Map::new();
+                obj.insert("type".to_string(), "array".into());
+                obj.insert("items".to_string(), self.canonical(items, namespace));
+                Value::Object(obj)
+            }
+            AvroType::Map { values, .. } => {
+                let mut obj = Map::new();
+                obj.insert("type".to_string(), "map".into());
+                obj.insert("values".to_string(), self.canonical(values, namespace));
+                Value::Object(obj)
+            }
+            AvroType::Union(types) => {
+                Value::Array(types.iter().map(|t| self.canonical(t, namespace)).collect())
+            }
+        }
+    }
+}
+
+/// Initial value and polynomial of the CRC-64-AVRO (Rabin) fingerprint.
+const CRC64_EMPTY: u64 = 0xc15d_213a_a4d7_a795;
+
+fn crc64_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut fp = i as u64;
+            for _ in 0..8 {
+                fp = (fp >> 1) ^ (CRC64_EMPTY & (fp & 1).wrapping_neg());
+            }
+            *entry = fp;
+        }
+        table
+    })
+}
+
+/// The CRC-64-AVRO (Rabin) fingerprint of `bytes`.
+pub fn crc64_avro(bytes: &[u8]) -> u64 {
+    let table = crc64_table();
+    bytes.iter().fold(CRC64_EMPTY, |fp, &b| {
+        (fp >> 8) ^ table[((fp ^ b as u64) & 0xff) as usize]
+    })
+}
+
+/// Fingerprints of a schema's Parsing Canonical Form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprints {
+    /// CRC-64-AVRO (Rabin) fingerprint, as used by single-object encoding.
+    pub crc64: u64,
+    pub md5: [u8; 16],
+    pub sha256: [u8; 32],
+}
+
+impl Fingerprints {
+    /// Fingerprint the canonical form of `schema`.
+    pub fn of(schema: &AvroType) -> Self {
+        let canonical = canonical_form(schema);
+        let bytes = canonical.as_bytes();
+        Fingerprints {
+            crc64: crc64_avro(bytes),
+            md5: Md5::digest(bytes).into(),
+            sha256: Sha256::digest(bytes).into(),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Serializes each fingerprint as lowercase hex of its bytes, the CRC-64
+/// little-endian as Avro writes it.
+impl Serialize for Fingerprints {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Fingerprints", 3)?;
+        s.serialize_field("crc64-avro", &hex(&self.crc64.to_le_bytes()))?;
+        s.serialize_field("md5", &hex(&self.md5))?;
+        s.serialize_field("sha256", &hex(&self.sha256))?;
+        s.end()
+    }
+}
@@ -0,0 +1,429 @@
+//! Schema evolution compatibility between two Avro schemas.
+//!
+//! Follows the Avro schema resolution rules: a reader schema can read data
+//! written with a writer schema if every writer type resolves to a reader
+//! type, with numeric promotions, reader defaults for fields the writer lacks,
+//! and aliases for renamed records, enums and fixed types.
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::AvroType;
+
+/// Which way data must flow between an old and a new schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Compatibility {
+    /// The new schema can read data written with the old one.
+    Backward,
+    /// The old schema can read data written with the new one.
+    Forward,
+    /// Both backward and forward.
+    #[default]
+    Full,
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compatibility::Backward => "BACKWARD",
+            Compatibility::Forward => "FORWARD",
+            Compatibility::Full => "FULL",
+        })
+    }
+}
+
+/// Why a reader schema cannot read data written with a writer schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Incompatibility {
+    /// The broken direction, [`Compatibility::Backward`] or
+    /// [`Compatibility::Forward`].
+    pub direction: Compatibility,
+    /// Where in the reader schema, e.g. `example.Person.address.items`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Incompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} at {}", self.direction, self.message, self.path)
+    }
+}
+
+/// Check whether `new` can replace `old` at the given compatibility `level`,
+/// returning every break found (none if compatible).
+pub fn check_compatibility(
+    old: &AvroType,
+    new: &AvroType,
+    level: Compatibility,
+) -> Vec<Incompatibility> {
+    let mut found = Vec::new();
+    if matches!(level, Compatibility::Backward | Compatibility::Full) {
+        found.extend(Resolver::new(new, old, Compatibility::Backward).run());
+    }
+    if matches!(level, Compatibility::Forward | Compatibility::Full) {
+        found.extend(Resolver::new(old, new, Compatibility::Forward).run());
+    }
+    found
+}
+
+/// A named type with the namespace its children are written in.
+type Named<'a> = (&'a AvroType, String);
+
+fn short_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Namespace of a named type written in `parent`.
+fn namespace_of(avro_type: &AvroType, parent: &str) -> String {
+    match avro_type {
+        AvroType::Record {
+            name, namespace, ..
+        }
+        | AvroType::Enum {
+            name, namespace, ..
+        }
+        | AvroType::Fixed {
+            name, namespace, ..
+        } => match name.rsplit_once('.') {
+            Some((ns, _)) => ns.to_string(),
+            None => namespace.clone().unwrap_or_else(|| parent.to_string()),
+        },
+        _ => parent.to_string(),
+    }
+}
+
+fn full_name(avro_type: &AvroType, parent: &str) -> String {
+    let name = short_name(avro_type.name().unwrap_or_default());
+    match namespace_of(avro_type, parent) {
+        ns if ns.is_empty() => name.to_string(),
+        ns => format!("{ns}.{name}"),
+    }
+}
+
+/// Index the named types of `avro_type` by full name.
+fn collect<'a>(avro_type: &'a AvroType, namespace: &str, names: &mut HashMap<String, Named<'a>>) {
+    match avro_type {
+        AvroType::Record { fields, .. } => {
+            let ns = namespace_of(avro_type, namespace);
+            names.insert(full_name(avro_type, namespace), (avro_type, ns.clone()));
+            for field in fields {
+                collect(&field.field_type, &ns, names);
+            }
+        }
+        AvroType::Enum { .. } | AvroType::Fixed { .. } => {
+            let ns = namespace_of(avro_type, namespace);
+            names.insert(full_name(avro_type, namespace), (avro_type, ns));
+        }
+        AvroType::Array { items: inner, .. }
+        | AvroType::Map { values: inner, .. }
+        | AvroType::Logical { base: inner, .. } => collect(inner, namespace, names),
+        AvroType::Union(types) => {
+            for t in types {
+                collect(t, namespace, names);
+            }
+        }
+        AvroType::Primitive { .. } | AvroType::Reference(_) => {}
+    }
+}
+
+/// Resolve references and strip logical types, which don't affect encoding.
+fn deref<'a>(
+    mut avro_type: &'a AvroType,
+    namespace: &str,
+    names: &HashMap<String, Named<'a>>,
+) -> Named<'a> {
+    let mut namespace = namespace.to_string();
+    loop {
+        match avro_type {
+            AvroType::Logical { base, .. } => avro_type = base,
+            AvroType::Reference(name) => {
+                let qualified = if name.contains('.') || namespace.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{namespace}.{name}")
+                };
+                match names.get(&qualified).or_else(|| names.get(name)) {
+                    Some((named, ns)) => {
+                        avro_type = named;
+                        namespace = ns.clone();
+                    }
+                    None => return (avro_type, namespace),
+                }
+            }
+            _ => return (avro_type, namespace),
+        }
+    }
+}
+
+/// Short description of a type for messages.
+fn describe(avro_type: &AvroType, namespace: &str) -> String {
+    match avro_type {
+        AvroType::Primitive { r#type, .. } => r#type.clone(),
+        AvroType::Array { .. } => "array".to_string(),
+        AvroType::Map { .. } => "map".to_string(),
+        AvroType::Union(_) => "union".to_string(),
+        AvroType::Reference(name) => name.clone(),
+        AvroType::Logical { base, .. } => describe(base, namespace),
+        named => full_name(named, namespace),
+    }
+}
+
+/// Whether a reader primitive can read a writer primitive, with promotion.
+fn promotes(reader: &str, writer: &str) -> bool {
+    reader == writer
+        || matches!(
+            (writer, reader),
+            ("int", "long" | "float" | "double")
+                | ("long", "float" | "double")
+                | ("float", "double")
+                | ("string", "bytes")
+                | ("bytes", "string")
+        )
+}
+
+struct Resolver<'a> {
+    reader: &'a AvroType,
+    writer: &'a AvroType,
+    reader_names: HashMap<String, Named<'a>>,
+    writer_names: HashMap<String, Named<'a>>,
+    direction: Compatibility,
+    /// Pairs of named types being compared, to stop at recursion.
+    visiting: HashSet<(String, String)>,
+    found: Vec<Incompatibility>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(reader: &'a AvroType, writer: &'a AvroType, direction: Compatibility) -> Self {
+        let mut reader_names = HashMap::new();
+        collect(reader, "", &mut reader_names);
+        let mut writer_names = HashMap::new();
+        collect(writer, "", &mut writer_names);
+        Resolver {
+            reader,
+            writer,
+            reader_names,
+            writer_names,
+            direction,
+            visiting: HashSet::new(),
+            found: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> Vec<Incompatibility> {
+        let path = match self.reader {
+            AvroType::Union(_) | AvroType::Primitive { .. } => String::new(),
+            reader => describe(reader, ""),
+        };
+        self.compare(self.reader, "", self.writer, "", &path);
+        self.found
+    }
+
+    fn report(&mut self, path: &str, message: String) {
+        self.found.push(Incompatibility {
+            direction: self.direction,
+            path: if path.is_empty() {
+                "(root)".to_string()
+            } else {
+                path.to_string()
+            },
+            message,
+        });
+    }
+
+    /// Whether named types match by unqualified name or a reader alias.
+    fn names_match(reader: &AvroType, writer: &AvroType) -> bool {
+        let aliases = match reader {
+            AvroType::Record { aliases, .. }
+            | AvroType::Enum { aliases, .. }
+            | AvroType::Fixed { aliases, .. } => aliases.as_slice(),
+            _ => &[],
+        };
+        let writer_name = short_name(writer.name().unwrap_or_default());
+        short_name(reader.name().unwrap_or_default()) == writer_name
+            || aliases.iter().any(|alias| short_name(alias) == writer_name)
+    }
+
+    /// The reader union branch a writer type resolves to: the first of the
+    /// same type (or name), else the first it can be promoted to.
+    fn select_branch(
+        &self,
+        branches: &'a [AvroType],
+        reader_ns: &str,
+        writer: &AvroType,
+    ) -> Option<&'a AvroType> {
+        let same = |branch: &AvroType| {
+            let (branch, _) = deref(branch, reader_ns, &self.reader_names);
+            match (branch, writer) {
+                (AvroType::Primitive { r#type: r, .. }, AvroType::Primitive { r#type: w, .. }) => {
+                    r == w
+                }
+                (AvroType::Array { .. }, AvroType::Array { .. })
+                | (AvroType::Map { .. }, AvroType::Map { .. }) => true,
+                (AvroType::Record { .. }, AvroType::Record { .. })
+                | (AvroType::Enum { .. }, AvroType::Enum { .. })
+                | (AvroType::Fixed { .. }, AvroType::Fixed { .. }) => {
+                    Self::names_match(branch, writer)
+                }
+                _ => false,
+            }
+        };
+        let promoted = |branch: &AvroType| {
+            let (branch, _) = deref(branch, reader_ns, &self.reader_names);
+            matches!(
+                (branch, writer),
+                (AvroType::Primitive { r#type: r, .. }, AvroType::Primitive { r#type: w, .. })
+                    if promotes(r, w)
+            )
+        };
+        branches
+            .iter()
+            .find(|b| same(b))
+            .or_else(|| branches.iter().find(|b| promoted(b)))
+    }
+
+    fn compare(
+        &mut self,
+        reader: &'a AvroType,
+        reader_ns: &str,
+        writer: &'a AvroType,
+        writer_ns: &str,
+        path: &str,
+    ) {
+        let (reader, reader_ns) = deref(reader, reader_ns, &self.reader_names);
+        let (writer, writer_ns) = deref(writer, writer_ns, &self.writer_names);
+        let cannot_read = |reader: &AvroType, writer: &AvroType| {
+            format!(
+                "writer type {} cannot be read as {}",
+                describe(writer, &writer_ns),
+                describe(reader, &reader_ns)
+            )
+        };
+        match (reader, writer) {
+            (_, AvroType::Union(branches)) => {
+                for branch in branches {
+                    self.compare(reader, &reader_ns, branch, &writer_ns, path);
+                }
+            }
+            (AvroType::Union(branches), _) => {
+                match self.select_branch(branches, &reader_ns, writer) {
+                    Some(branch) => self.compare(branch, &reader_ns, writer, &writer_ns, path),
+                    None => {
+                        let message = format!(
+                            "no branch of the reader union can read writer type {}",
+                            describe(writer, &writer_ns)
+                        );
+                        self.report(path, message);
+                    }
+                }
+            }
+            (AvroType::Primitive { r#type: r, .. }, AvroType::Primitive { r#type: w, .. }) => {
+                if !promotes(r, w) {
+                    self.report(path, cannot_read(reader, writer));
+                }
+            }
+            (AvroType::Array { items: r, .. }, AvroType::Array { items: w, .. }) => {
+                self.compare(r, &reader_ns, w, &writer_ns, &format!("{path}.items"));
+            }
+            (AvroType::Map { values: r, .. }, AvroType::Map { values: w, .. }) => {
+                self.compare(r, &reader_ns, w, &writer_ns, &format!("{path}.values"));
+            }
+            (AvroType::Record { .. }, AvroType::Record { .. })
+            | (AvroType::Enum { .. }, AvroType::Enum { .. })
+            | (AvroType::Fixed { .. }, AvroType::Fixed { .. }) => {
+                if !Self::names_match(reader, writer) {
+                    let message = format!(
+                        "{} was renamed from {} without an alias",
+                        describe(reader, &reader_ns),
+                        describe(writer, &writer_ns)
+                    );
+                    self.report(path, message);
+                    return;
+                }
+                self.compare_named(reader, &reader_ns, writer, &writer_ns, path);
+            }
+            _ => self.report(path, cannot_read(reader, writer)),
+        }
+    }
+
+    /// Compare named types of the same kind and matching names.
+    fn compare_named(
+        &mut self,
+        reader: &'a AvroType,
+        reader_ns: &str,
+        writer: &'a AvroType,
+        writer_ns: &str,
+        path: &str,
+    ) {
+        match (reader, writer) {
+            (
+                AvroType::Record {
+                    fields: reader_fields,
+                    ..
+                },
+                AvroType::Record {
+                    fields: writer_fields,
+                    ..
+                },
+            ) => {
+                let pair = (full_name(reader, reader_ns), full_name(writer, writer_ns));
+                if !self.visiting.insert(pair.clone()) {
+                    return;
+                }
+                for field in reader_fields {
+                    let field_path = format!("{path}.{}", field.name);
+                    let written = writer_fields
+                        .iter()
+                        .find(|w| w.name == field.name || field.aliases.contains(&w.name));
+                    match written {
+                        Some(written) => self.compare(
+                            &field.field_type,
+                            reader_ns,
+                            &written.field_type,
+                            writer_ns,
+                            &field_path,
+                        ),
+                        None if field.default.is_none() => {
+                            let message = format!(
+                                "reader field {} is missing from the writer and has no default",
+                                field.name
+                            );
+                            self.report(&field_path, message);
+                        }
+                        None => {}
+                    }
+                }
+                self.visiting.remove(&pair);
+            }
+            (
+                AvroType::Enum {
+                    symbols: reader_symbols,
+                    default,
+                    ..
+                },
+                AvroType::Enum {
+                    symbols: writer_symbols,
+                    ..
+                },
+            ) => {
+                if default.is_some() {
+                    return;
+                }
+                for symbol in writer_symbols {
+                    if !reader_symbols.contains(symbol) {
+                        let message = format!(
+                            "writer symbol {symbol} is missing from the reader enum, which has no default"
+                        );
+                        self.report(path, message);
+                    }
+                }
+            }
+            (AvroType::Fixed { size: r, .. }, AvroType::Fixed { size: w, .. }) if r != w => {
+                let message = format!("fixed size changed from {w} to {r}");
+                self.report(path, message);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Rendering of Avro schemas as Avro IDL (`.avdl`).
+//!
+//! IDL declares every named type at the top level, so types defined inline
+//! in the schema are hoisted out ahead of the type that first uses them. The
+//! order of the schema is otherwise kept: converter output lists types in
+//! dependency order, and the declarations follow it.
+use serde_json::Value;
+
+use super::{Attributes, AvroField, AvroType};
+
+/// Words that must be escaped with backticks when used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "array",
+    "boolean",
+    "bytes",
+    "date",
+    "decimal",
+    "double",
+    "enum",
+    "error",
+    "false",
+    "fixed",
+    "float",
+    "idl",
+    "import",
+    "int",
+    "local_timestamp_ms",
+    "long",
+    "map",
+    "namespace",
+    "null",
+    "oneway",
+    "protocol",
+    "record",
+    "schema",
+    "string",
+    "throws",
+    "time_ms",
+    "timestamp_ms",
+    "true",
+    "union",
+    "uuid",
+    "void",
+];
+
+/// Render `schema` as an Avro IDL file.
+///
+/// With a `protocol` name the types are wrapped in a `protocol` declaration,
+/// as older IDL tooling requires; otherwise an IDL schema file is written,
+/// whose `schema` is the root type when there is a single one.
+pub fn to_idl(schema: &AvroType, protocol: Option<&str>) -> String {
+    let top_level: Vec<&AvroType> = match schema {
+        AvroType::Union(types) => types.iter().collect(),
+        other => vec![other],
+    };
+    let namespace = top_level
+        .iter()
+        .find(|t| t.name().is_some())
+        .map(|t| namespace_of(t, ""))
+        .unwrap_or_default();
+    let mut writer = IdlWriter {
+        namespace: namespace.clone(),
+        declarations: Vec::new(),
+    };
+    for t in &top_level {
+        writer.hoist(t, &namespace);
+    }
+
+    let mut out = String::new();
+    let indent = match protocol {
+        Some(name) => {
+            if !namespace.is_empty() {
+                out.push_str(&format!("@namespace({})\n", json(namespace.as_str())));
+            }
+            out.push_str(&format!("protocol {} {{\n", ident(name)));
+            "  "
+        }
+        None => {
+            if !namespace.is_empty() {
+                out.push_str(&format!("namespace {namespace};\n"));
+            }
+            if let [root] = top_level.as_slice() {
+                let root = writer.type_ref(root, &namespace);
+                out.push_str(&format!("schema {root};\n"));
+            }
+            ""
+        }
+    };
+    for declaration in &writer.declarations {
+        out.push('\n');
+        for line in declaration.lines() {
+            if !line.is_empty() {
+                out.push_str(indent);
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    if protocol.is_some() {
+        out.push_str("}\n");
+    }
+    out
+}
+
+fn json(value: impl Into<Value>) -> String {
+    serde_json::to_string(&value.into()).unwrap()
+}
+
+/// An identifier, escaped if it is a keyword.
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("`{name}`")
+    } else {
+        name.to_string()
+    }
+}
+
+/// A name that may contain dots, each part escaped if it is a keyword.
+fn qualified_ident(name: &str) -> String {
+    name.split('.').map(ident).collect::<Vec<_>>().join(".")
+}
+
+/// A `/** doc */` comment, on its own lines when the doc spans several.
+fn doc_comment(doc: &Option<String>, indent: &str) -> String {
+    let Some(doc) = doc else {
+        return String::new();
+    };
+    let doc = doc.replace("*/", "*\\/");
+    if doc.contains('\n') {
+        let mut out = format!("{indent}/**\n");
+        for line in doc.lines() {
+            if line.is_empty() {
+                out.push_str(&format!("{indent} *\n"));
+            } else {
+                out.push_str(&format!("{indent} * {line}\n"));
+            }
+        }
+        out.push_str(&format!("{indent} */\n"));
+        out
+    } else {
+        format!("{indent}/** {doc} */\n")
+    }
+}
+
+/// `@key(value)` annotations for custom attributes, each followed by a space.
+fn annotations(attributes: &Attributes) -> String {
+    attributes
+        .iter()
+        .map(|(key, value)| format!("@{key}({}) ", json(value.clone())))
+        .collect()
+}
+
+/// Namespace of a named type written in `parent`.
+fn namespace_of(avro_type: &AvroType, parent: &str) -> String {
+    match avro_type {
+        AvroType::Record {
+            name, namespace, ..
+        }
+        | AvroType::Enum {
+            name, namespace, ..
+        }
+        | AvroType::Fixed {
+            name, namespace, ..
+        } => match name.rsplit_once('.') {
+            Some((ns, _)) => ns.to_string(),
+            None => namespace.clone().unwrap_or_else(|| parent.to_string()),
+        },
+        AvroType::Logical { base, .. } => namespace_of(base, parent),
+        _ => parent.to_string(),
+    }
+}
+
+fn short_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// The IDL shorthand for a logical type, if it has one.
+fn logical_shorthand(
+    logical_type: &str,
+    base: &AvroType,
+    attributes: &Attributes,
+) -> Option<String> {
+    let AvroType::Primitive {
+        r#type,
+        attributes: base_attributes,
+    } = base
+    else {
+        return None;
+    };
+    if !base_attributes.is_empty() {
+        return None;
+    }
+    let plain = attributes.is_empty();
+    Some(match (logical_type, r#type.as_str()) {
+        ("date", "int") if plain => "date".to_string(),
+        ("time-millis", "int") if plain => "time_ms".to_string(),
+        ("timestamp-millis", "long") if plain => "timestamp_ms".to_string(),
+        ("local-timestamp-millis", "long") if plain => "local_timestamp_ms".to_string(),
+        ("uuid", "string") if plain => "uuid".to_string(),
+        ("decimal", "bytes") => {
+            let precision = attributes.get("precision")?.as_u64()?;
+            let scale = attributes.get("scale").map_or(Some(0), Value::as_u64)?;
+            if attributes.keys().any(|k| k != "precision" && k != "scale") {
+                return None;
+            }
+            format!("decimal({precision}, {scale})")
+        }
+        _ => return None,
+    })
+}
+
+struct IdlWriter {
+    /// Namespace of the file or protocol, in which short names resolve.
+    namespace: String,
+    /// Named type declarations, in order.
+    declarations: Vec<String>,
+}
+
+impl IdlWriter {
+    /// How to refer to the named type `full_name` from a declaration in
+    /// `namespace`: by short name only where both are in the file's namespace.
+    fn name_ref(&self, full_name: &str, namespace: &str) -> String {
+        match full_name.rsplit_once('.') {
+            Some((ns, short)) if ns == self.namespace && namespace == self.namespace => {
+                ident(short)
+            }
+            _ => qualified_ident(full_name),
+        }
+    }
+
+    fn full_name(avro_type: &AvroType, namespace: &str) -> String {
+        let name = short_name(avro_type.name().unwrap_or_default());
+        match namespace_of(avro_type, namespace) {
+            ns if ns.is_empty() => name.to_string(),
+            ns => format!("{ns}.{name}"),
+        }
+    }
+
+    /// The inline IDL type expression for `avro_type`.
+    fn type_ref(&self, avro_type: &AvroType, namespace: &str) -> String {
+        match avro_type {
+            AvroType::Primitive { r#type, attributes } => {
+                format!("{}{}", annotations(attributes), r#type)
+            }
+            AvroType::Reference(name) => {
+                if name.contains('.') || namespace.is_empty() {
+                    self.name_ref(name, namespace)
+                } else {
+                    self.name_ref(&format!("{namespace}.{name}"), namespace)
+                }
+            }
+            AvroType::Record { .. } | AvroType::Enum { .. } | AvroType::Fixed { .. } => {
+                self.name_ref(&Self::full_name(avro_type, namespace), namespace)
+            }
+            AvroType::Array { items, attributes } => format!(
+                "{}array<{}>",
+                annotations(attributes),
+                self.type_ref(items, namespace)
+            ),
+            AvroType::Map { values, attributes } => format!(
+                "{}map<{}>",
+                annotations(attributes),
+                self.type_ref(values, namespace)
+            ),
+            AvroType::Union(types) => format!(
+                "union {{ {} }}",
+                types
+                    .iter()
+                    .map(|t| self.type_ref(t, namespace))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => {
+                if let AvroType::Fixed { .. } = **base {
+                    // Annotated on the fixed declaration
+                    return self.type_ref(base, namespace);
+                }
+                if let Some(shorthand) = logical_shorthand(logical_type, base, attributes) {
+                    return shorthand;
+                }
+                format!(
+                    "@logicalType({}) {}{}",
+                    json(logical_type.as_str()),
+                    annotations(attributes),
+                    self.type_ref(base, namespace)
+                )
+            }
+        }
+    }
+
+    /// Declare the named types in `avro_type`, each after those it contains.
+    fn hoist(&mut self, avro_type: &AvroType, namespace: &str) {
+        match avro_type {
+            AvroType::Record {
+                aliases,
+                doc,
+                fields,
+                attributes,
+                ..
+            } => {
+                let ns = namespace_of(avro_type, namespace);
+                for field in fields {
+                    self.hoist(&field.field_type, &ns);
+                }
+                let mut out = doc_comment(doc, "");
+                out.push_str(&self.named_annotations(&ns, aliases, attributes));
+                out.push_str(&format!(
+                    "record {} {{\n",
+                    ident(short_name(avro_type.name().unwrap_or_default()))
+                ));
+                for field in fields {
+                    out.push_str(&self.field(field, &ns));
+                }
+                out.push_str("}\n");
+                self.declarations.push(out);
+            }
+            AvroType::Enum {
+                aliases,
+                doc,
+                symbols,
+                default,
+                attributes,
+                ..
+            } => {
+                let ns = namespace_of(avro_type, namespace);
+                let mut out = doc_comment(doc, "");
+                out.push_str(&self.named_annotations(&ns, aliases, attributes));
+                out.push_str(&format!(
+                    "enum {} {{\n  {}\n}}",
+                    ident(short_name(avro_type.name().unwrap_or_default())),
+                    symbols
+                        .iter()
+                        .map(|s| ident(s))
+                        .collect::<Vec<_>>()
+                        .join(",\n  ")
+                ));
+                if let Some(default) = default {
+                    out.push_str(&format!(" = {}", ident(default)));
+                }
+                out.push_str(";\n");
+                self.declarations.push(out);
+            }
+            AvroType::Fixed { .. } => self.declare_fixed(avro_type, namespace, ""),
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => {
+                if let AvroType::Fixed { .. } = **base {
+                    let logical = format!(
+                        "@logicalType({}) {}",
+                        json(logical_type.as_str()),
+                        annotations(attributes)
+                    );
+                    self.declare_fixed(base, namespace, &logical);
+                } else {
+                    self.hoist(base, namespace);
+                }
+            }
+            AvroType::Array { items: inner, .. } | AvroType::Map { values: inner, .. } => {
+                self.hoist(inner, namespace)
+            }
+            AvroType::Union(types) => {
+                for t in types {
+                    self.hoist(t, namespace);
+                }
+            }
+            AvroType::Primitive { .. } | AvroType::Reference(_) => {}
+        }
+    }
+
+    fn declare_fixed(&mut self, fixed: &AvroType, namespace: &str, logical: &str) {
+        let AvroType::Fixed {
+            aliases,
+            size,
+            attributes,
+            ..
+        } = fixed
+        else {
+            return;
+        };
+        let ns = namespace_of(fixed, namespace);
+        let mut out = self.named_annotations(&ns, aliases, attributes);
+        out.push_str(logical.trim_end());
+        if !logical.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "fixed {}({size});\n",
+            ident(short_name(fixed.name().unwrap_or_default()))
+        ));
+        self.declarations.push(out);
+    }
+
+    /// `@namespace`, `@aliases` and custom annotations of a named type, one
+    /// per line.
+    fn named_annotations(
+        &self,
+        namespace: &str,
+        aliases: &[String],
+        attributes: &Attributes,
+    ) -> String {
+        let mut out = String::new();
+        if namespace != self.namespace {
+            out.push_str(&format!("@namespace({})\n", json(namespace)));
+        }
+        if !aliases.is_empty() {
+            out.push_str(&format!("@aliases({})\n", json(aliases.to_vec())));
+        }
+        for (key, value) in attributes {
+            out.push_str(&format!("@{key}({})\n", json(value.clone())));
+        }
+        out
+    }
+
+    fn field(&self, field: &AvroField, namespace: &str) -> String {
+        let mut out = doc_comment(&field.doc, "  ");
+        out.push_str(&format!(
+            "  {} ",
+            self.type_ref(&field.field_type, namespace)
+        ));
+        if !field.aliases.is_empty() {
+            out.push_str(&format!("@aliases({}) ", json(field.aliases.clone())));
+        }
+        out.push_str(&annotations(&field.attributes));
+        out.push_str(&ident(&field.name));
+        if let Some(default) = &field.default {
+            out.push_str(&format!(" = {}", json(default.clone())));
+        }
+        out.push_str(";\n");
+        out
+    }
+}
@@ -0,0 +1,307 @@
+//! Checks that an [`AvroType`] is a valid Avro schema.
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::{AvroField, AvroType, PRIMITIVE_TYPES};
+use crate::common::names::{is_avro_name, is_avro_namespace};
+use crate::error::Error;
+
+/// Check `schema` against the rules of the Avro specification.
+///
+/// Names and namespaces must be legal, full names unique, and every named
+/// reference must come after the definition it refers to. Unions may not
+/// directly contain a union, nor two types of the same kind (two arrays, two
+/// `int`s, two records of the same name). Enum symbols must be valid and
+/// unique, and defaults must match their type (the first branch of a union).
+pub fn validate(schema: &AvroType) -> Result<(), Error> {
+    Validator::default().check(schema, "", "")
+}
+
+/// Check `schemas` written as separate documents, like [`validate`] but
+/// letting each refer to the top-level types of the others by full name, as
+/// a schema registry resolves them.
+pub fn validate_documents(schemas: &[AvroType]) -> Result<(), Error> {
+    let mut validator = Validator::default();
+    for schema in schemas {
+        if let Some(full_name) = schema.full_name() {
+            validator.external.insert(full_name, schema);
+        }
+    }
+    for schema in schemas {
+        validator.check(schema, "", "")?;
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Validator<'a> {
+    /// Named types by full name, in the order they were defined.
+    defined: HashMap<String, &'a AvroType>,
+    /// Named types defined in other documents, referable from anywhere.
+    external: HashMap<String, &'a AvroType>,
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidSchema { message }
+}
+
+fn invalid_name(name: &str) -> Error {
+    Error::InvalidName {
+        name: name.to_string(),
+    }
+}
+
+/// Full name of `name` written in `namespace`.
+fn qualify(name: &str, namespace: &str) -> String {
+    if name.contains('.') || namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{namespace}.{name}")
+    }
+}
+
+/// Where a problem was found, for error messages.
+fn at(context: &str) -> String {
+    if context.is_empty() {
+        String::new()
+    } else {
+        format!(" in {context}")
+    }
+}
+
+impl<'a> Validator<'a> {
+    fn check(
+        &mut self,
+        avro_type: &'a AvroType,
+        namespace: &str,
+        context: &str,
+    ) -> Result<(), Error> {
+        match avro_type {
+            AvroType::Primitive { .. } => Ok(()),
+            AvroType::Reference(name) => self.resolve(name, namespace, context).map(|_| ()),
+            AvroType::Record {
+                name,
+                namespace: ns,
+                aliases,
+                fields,
+                ..
+            } => {
+                let (full_name, namespace) =
+                    self.define(avro_type, name, ns, aliases, namespace)?;
+                let mut seen: Vec<&str> = Vec::new();
+                for field in fields {
+                    let context = format!("{full_name}.{}", field.name);
+                    if !is_avro_name(&field.name) {
+                        return Err(invalid_name(&field.name));
+                    }
+                    if let Some(alias) = field.aliases.iter().find(|a| !is_avro_name(a)) {
+                        return Err(invalid_name(alias));
+                    }
+                    if seen.contains(&field.name.as_str()) {
+                        return Err(invalid(format!("duplicate field {context}")));
+                    }
+                    seen.push(&field.name);
+                    self.check(&field.field_type, &namespace, &context)?;
+                    if let Some(default) = &field.default {
+                        self.check_default(&field.field_type, default, &namespace, &context)?;
+                    }
+                }
+                Ok(())
+            }
+            AvroType::Enum {
+                name,
+                namespace: ns,
+                aliases,
+                symbols,
+                default,
+                ..
+            } => {
+                let (full_name, _) = self.define(avro_type, name, ns, aliases, namespace)?;
+                if let Some(symbol) = symbols.iter().find(|s| !is_avro_name(s)) {
+                    return Err(invalid_name(symbol));
+                }
+                for (i, symbol) in symbols.iter().enumerate() {
+                    if symbols[..i].contains(symbol) {
+                        return Err(invalid(format!(
+                            "duplicate symbol {symbol} in enum {full_name}"
+                        )));
+                    }
+                }
+                match default {
+                    Some(default) if !symbols.contains(default) => Err(invalid(format!(
+                        "default {default} of enum {full_name} is not one of its symbols"
+                    ))),
+                    _ => Ok(()),
+                }
+            }
+            AvroType::Fixed {
+                name,
+                namespace: ns,
+                aliases,
+                ..
+            } => self
+                .define(avro_type, name, ns, aliases, namespace)
+                .map(|_| ()),
+            AvroType::Array { items: inner, .. }
+            | AvroType::Map { values: inner, .. }
+            | AvroType::Logical { base: inner, .. } => self.check(inner, namespace, context),
+            AvroType::Union(types) => {
+                let mut kinds: Vec<String> = Vec::new();
+                for t in types {
+                    self.check(t, namespace, context)?;
+                    let kind = self.kind(t, namespace, context)?;
+                    if kinds.contains(&kind) {
+                        return Err(invalid(format!(
+                            "union contains two {kind} types{}",
+                            at(context)
+                        )));
+                    }
+                    kinds.push(kind);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Register a named type, returning its full name and the namespace its
+    /// children are written in.
+    fn define(
+        &mut self,
+        avro_type: &'a AvroType,
+        name: &str,
+        namespace: &Option<String>,
+        aliases: &[String],
+        parent_namespace: &str,
+    ) -> Result<(String, String), Error> {
+        let (ns, short) = match name.rsplit_once('.') {
+            Some((ns, short)) => (ns, short),
+            None => (namespace.as_deref().unwrap_or(parent_namespace), name),
+        };
+        if !is_avro_name(short) || PRIMITIVE_TYPES.contains(&short) {
+            return Err(invalid_name(name));
+        }
+        if !is_avro_namespace(ns) {
+            return Err(invalid_name(ns));
+        }
+        if let Some(alias) = aliases
+            .iter()
+            .find(|a| !is_avro_name(a.rsplit('.').next().unwrap_or(a)))
+        {
+            return Err(invalid_name(alias));
+        }
+        let full_name = qualify(short, ns);
+        if self.defined.insert(full_name.clone(), avro_type).is_some() {
+            return Err(Error::NameCollision { name: full_name });
+        }
+        Ok((full_name, ns.to_string()))
+    }
+
+    /// The named type `name` refers to, which must already be defined.
+    fn resolve(
+        &self,
+        name: &str,
+        namespace: &str,
+        context: &str,
+    ) -> Result<(String, &'a AvroType), Error> {
+        [qualify(name, namespace), name.to_string()]
+            .into_iter()
+            .find_map(|full_name| {
+                let named = self
+                    .defined
+                    .get(&full_name)
+                    .or_else(|| self.external.get(&full_name))
+                    .copied()?;
+                Some((full_name, named))
+            })
+            .ok_or_else(|| invalid(format!("reference to undefined type {name}{}", at(context))))
+    }
+
+    /// What a union branch is distinguished by: the full name of a named
+    /// type, or the kind of an unnamed one.
+    fn kind(&self, avro_type: &AvroType, namespace: &str, context: &str) -> Result<String, Error> {
+        Ok(match avro_type {
+            AvroType::Primitive { r#type, .. } => r#type.clone(),
+            AvroType::Array { .. } => "array".to_string(),
+            AvroType::Map { .. } => "map".to_string(),
+            AvroType::Logical { base, .. } => self.kind(base, namespace, context)?,
+            AvroType::Reference(name) => self.resolve(name, namespace, context)?.0,
+            AvroType::Union(_) => {
+                return Err(invalid(format!(
+                    "union directly contains a union{}",
+                    at(context)
+                )))
+            }
+            named => qualify(&named.full_name().unwrap_or_default(), namespace),
+        })
+    }
+
+    fn check_default(
+        &self,
+        avro_type: &AvroType,
+        default: &Value,
+        namespace: &str,
+        context: &str,
+    ) -> Result<(), Error> {
+        if self.matches(avro_type, default, namespace) {
+            Ok(())
+        } else {
+            Err(invalid(format!(
+                "default {default} does not match the type{}",
+                at(context)
+            )))
+        }
+    }
+
+    /// Whether `value` is a valid default for `avro_type`.
+    fn matches(&self, avro_type: &AvroType, value: &Value, namespace: &str) -> bool {
+        match avro_type {
+            AvroType::Primitive { r#type, .. } => match r#type.as_str() {
+                "null" => value.is_null(),
+                "boolean" => value.is_boolean(),
+                "int" => value.as_i64().is_some_and(|n| i32::try_from(n).is_ok()),
+                "long" => value.is_i64(),
+                "float" | "double" => value.is_number(),
+                "bytes" | "string" => value.is_string(),
+                _ => false,
+            },
+            AvroType::Fixed { size, .. } => value
+                .as_str()
+                .is_some_and(|s| s.chars().count() as u64 == *size),
+            AvroType::Enum { symbols, .. } => value
+                .as_str()
+                .is_some_and(|s| symbols.iter().any(|symbol| symbol == s)),
+            AvroType::Array { items, .. } => value
+                .as_array()
+                .is_some_and(|values| values.iter().all(|v| self.matches(items, v, namespace))),
+            AvroType::Map { values, .. } => value.as_object().is_some_and(|entries| {
+                entries.values().all(|v| self.matches(values, v, namespace))
+            }),
+            AvroType::Record {
+                name,
+                namespace: ns,
+                fields,
+                ..
+            } => {
+                let namespace = match name.rsplit_once('.') {
+                    Some((ns, _)) => ns,
+                    None => ns.as_deref().unwrap_or(namespace),
+                };
+                value.as_object().is_some_and(|entries| {
+                    fields
+                        .iter()
+                        .all(|field: &AvroField| match entries.get(&field.name) {
+                            Some(v) => self.matches(&field.field_type, v, namespace),
+                            None => field.default.is_some(),
+                        })
+                })
+            }
+            AvroType::Logical { base, .. } => self.matches(base, value, namespace),
+            AvroType::Union(types) => types
+                .first()
+                .is_some_and(|first| self.matches(first, value, namespace)),
+            AvroType::Reference(name) => self
+                .resolve(name, namespace, "")
+                .is_ok_and(|(_, named)| self.matches(named, value, namespace)),
+        }
+    }
+}
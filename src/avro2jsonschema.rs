@@ -0,0 +1,392 @@
+//! Conversion from Avro schema back to JSON Schema.
+//!
+//! The inverse of [`jsons_to_avro`](crate::converter::jsons_to_avro), in the
+//! vocabulary the forward conversion reads (see
+//! [`generic_type_json`](crate::common::generic_type_json)):
+//!
+//! - records become objects, with the fields whose type isn't a nullable
+//!   union `required`;
+//! - enums become string `enum`s, maps `additionalProperties` and unions
+//!   `oneOf` (or a `"null"` added to `type`);
+//! - logical types become `format`s, and decimals `x-avro-decimal`;
+//! - fields and enum symbols renamed from JSON get their names back from
+//!   `altnames` and `altsymbols`;
+//! - named types become `$defs` entries referred to by `$ref`, except the root
+//!   type, which is the schema itself.
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::avro::{AvroField, AvroType};
+use crate::converter::structs::ALTNAME_PURPOSE;
+
+/// The JSON Schema dialect of the output.
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Convert an Avro schema into an equivalent JSON Schema.
+///
+/// For a list of top-level types the last one is the root, and the others
+/// are only reachable through `$defs`.
+pub fn avro_to_jsons(avro_schema: &AvroType) -> Value {
+    let top_level: Vec<&AvroType> = match avro_schema {
+        AvroType::Union(types) => types.iter().collect(),
+        other => vec![other],
+    };
+    let Some(root) = top_level.last() else {
+        return json!({"$schema": DIALECT, "not": {}});
+    };
+
+    let mut named = Vec::new();
+    for t in &top_level {
+        collect(t, "", &mut named);
+    }
+    let root_name = root.name().map(|_| full_name(root, ""));
+    let mut keys = HashMap::new();
+    for (full_name, _, _) in &named {
+        let short = full_name.rsplit('.').next().unwrap_or(full_name);
+        let unique = named
+            .iter()
+            .filter(|(other, _, _)| other.rsplit('.').next() == Some(short))
+            .count()
+            == 1;
+        let key = if unique { short } else { full_name.as_str() };
+        keys.insert(full_name.clone(), key.to_string());
+    }
+    let converter = Reverse {
+        keys: &keys,
+        root: root_name.clone(),
+    };
+
+    let mut schema = Map::new();
+    schema.insert("$schema".to_string(), DIALECT.into());
+    if let Some(name) = root.name() {
+        let short = name.rsplit('.').next().unwrap_or(name);
+        schema.insert("title".to_string(), short.into());
+    }
+    match converter.definition(root, "") {
+        Value::Object(root) => schema.extend(root),
+        other => {
+            schema.insert("allOf".to_string(), json!([other]));
+        }
+    }
+    let defs: Map<String, Value> = named
+        .iter()
+        .filter(|(full_name, _, _)| Some(full_name) != root_name.as_ref())
+        .map(|(full_name, avro_type, namespace)| {
+            (
+                keys[full_name].clone(),
+                converter.definition(avro_type, namespace),
+            )
+        })
+        .collect();
+    if !defs.is_empty() {
+        schema.insert("$defs".to_string(), Value::Object(defs));
+    }
+    Value::Object(schema)
+}
+
+/// Namespace of a named type written in `parent`.
+fn namespace_of(avro_type: &AvroType, parent: &str) -> String {
+    match avro_type {
+        AvroType::Record {
+            name, namespace, ..
+        }
+        | AvroType::Enum {
+            name, namespace, ..
+        }
+        | AvroType::Fixed {
+            name, namespace, ..
+        } => match name.rsplit_once('.') {
+            Some((ns, _)) => ns.to_string(),
+            None => namespace.clone().unwrap_or_else(|| parent.to_string()),
+        },
+        AvroType::Logical { base, .. } => namespace_of(base, parent),
+        _ => parent.to_string(),
+    }
+}
+
+fn full_name(avro_type: &AvroType, parent: &str) -> String {
+    let name = avro_type.name().unwrap_or_default();
+    let short = name.rsplit('.').next().unwrap_or(name);
+    match namespace_of(avro_type, parent) {
+        ns if ns.is_empty() => short.to_string(),
+        ns => format!("{ns}.{short}"),
+    }
+}
+
+/// List the named types of `avro_type` with their full names and the
+/// namespace they are written in.
+fn collect<'a>(
+    avro_type: &'a AvroType,
+    namespace: &str,
+    named: &mut Vec<(String, &'a AvroType, String)>,
+) {
+    match avro_type {
+        AvroType::Record { fields, .. } => {
+            named.push((
+                full_name(avro_type, namespace),
+                avro_type,
+                namespace.to_string(),
+            ));
+            let ns = namespace_of(avro_type, namespace);
+            for field in fields {
+                collect(&field.field_type, &ns, named);
+            }
+        }
+        AvroType::Enum { .. } | AvroType::Fixed { .. } => {
+            named.push((
+                full_name(avro_type, namespace),
+                avro_type,
+                namespace.to_string(),
+            ));
+        }
+        AvroType::Logical { base, .. } if base.name().is_some() => {
+            // A logical type on a fixed is defined along with it
+            named.push((full_name(base, namespace), avro_type, namespace.to_string()));
+        }
+        AvroType::Array { items: inner, .. } | AvroType::Map { values: inner, .. } => {
+            collect(inner, namespace, named)
+        }
+        AvroType::Union(types) => {
+            for t in types {
+                collect(t, namespace, named);
+            }
+        }
+        AvroType::Primitive { .. } | AvroType::Logical { .. } | AvroType::Reference(_) => {}
+    }
+}
+
+fn is_null(avro_type: &AvroType) -> bool {
+    matches!(avro_type, AvroType::Primitive { r#type, .. } if r#type == "null")
+}
+
+struct Reverse<'a> {
+    /// `$defs` key of each named type, by full name.
+    keys: &'a HashMap<String, String>,
+    /// Full name of the root type, referred to as `#`.
+    root: Option<String>,
+}
+
+impl Reverse<'_> {
+    /// `$ref` to the named type `full_name`.
+    fn reference(&self, full_name: &str) -> Value {
+        if self.root.as_deref() == Some(full_name) {
+            return json!({"$ref": "#"});
+        }
+        match self.keys.get(full_name) {
+            Some(key) => {
+                json!({"$ref": format!("#/$defs/{}", key.replace('~', "~0").replace('/', "~1"))})
+            }
+            None => json!({}),
+        }
+    }
+
+    /// Where a type is used: named types are referred to, others inlined.
+    fn schema(&self, avro_type: &AvroType, namespace: &str) -> Value {
+        match avro_type {
+            AvroType::Record { .. } | AvroType::Enum { .. } | AvroType::Fixed { .. } => {
+                self.reference(&full_name(avro_type, namespace))
+            }
+            AvroType::Logical { base, .. } if base.name().is_some() => {
+                self.reference(&full_name(base, namespace))
+            }
+            AvroType::Reference(name) => {
+                let qualified = if name.contains('.') || namespace.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{namespace}.{name}")
+                };
+                if self.keys.contains_key(&qualified) || self.root.as_ref() == Some(&qualified) {
+                    self.reference(&qualified)
+                } else {
+                    self.reference(name)
+                }
+            }
+            other => self.definition(other, namespace),
+        }
+    }
+
+    /// The JSON Schema for `avro_type` itself.
+    fn definition(&self, avro_type: &AvroType, namespace: &str) -> Value {
+        match avro_type {
+            AvroType::Primitive { r#type, attributes } => {
+                let mut schema = match r#type.as_str() {
+                    "null" => json!({"type": "null"}),
+                    "boolean" => json!({"type": "boolean"}),
+                    "int" => json!({"type": "integer", "format": "int32"}),
+                    "long" => json!({"type": "integer", "format": "int64"}),
+                    "float" => json!({"type": "number", "format": "float"}),
+                    "double" => json!({"type": "number", "format": "double"}),
+                    "bytes" => json!({"type": "string", "format": "byte"}),
+                    _ => json!({"type": "string"}),
+                };
+                for keyword in ["format", "enum"] {
+                    if let Some(value) = attributes.get(keyword) {
+                        schema[keyword] = value.clone();
+                    }
+                }
+                schema
+            }
+            AvroType::Record { doc, fields, .. } => {
+                let ns = namespace_of(avro_type, namespace);
+                let mut properties = Map::new();
+                let mut required = Vec::new();
+                for field in fields {
+                    let (schema, optional) = self.field(field, &ns);
+                    let property = field.altname(ALTNAME_PURPOSE);
+                    if !optional {
+                        required.push(Value::String(property.to_string()));
+                    }
+                    properties.insert(property.to_string(), schema);
+                }
+                let mut schema = json!({"type": "object", "properties": properties});
+                if !required.is_empty() {
+                    schema["required"] = Value::Array(required);
+                }
+                if let Some(doc) = doc {
+                    schema["description"] = doc.clone().into();
+                }
+                schema
+            }
+            AvroType::Enum {
+                doc,
+                symbols,
+                default,
+                ..
+            } => {
+                let value = |symbol: &String| {
+                    avro_type
+                        .altsymbol(ALTNAME_PURPOSE, symbol)
+                        .cloned()
+                        .unwrap_or_else(|| Value::String(symbol.clone()))
+                };
+                let values: Vec<Value> = symbols.iter().map(value).collect();
+                let mut schema = if values.iter().all(Value::is_string) {
+                    json!({"type": "string", "enum": values})
+                } else {
+                    json!({"enum": values})
+                };
+                if let Some(default) = default {
+                    schema["default"] = value(default);
+                }
+                if let Some(doc) = doc {
+                    schema["description"] = doc.clone().into();
+                }
+                schema
+            }
+            AvroType::Fixed { size, .. } => json!({
+                "type": "string",
+                "format": "binary",
+                "minLength": size,
+                "maxLength": size
+            }),
+            AvroType::Array { items, .. } => json!({
+                "type": "array",
+                "items": self.schema(items, namespace)
+            }),
+            AvroType::Map { values, .. } => json!({
+                "type": "object",
+                "additionalProperties": self.schema(values, namespace)
+            }),
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => self.logical(logical_type, base, attributes, namespace),
+            AvroType::Union(types) => self.union(types, namespace),
+            AvroType::Reference(_) => self.schema(avro_type, namespace),
+        }
+    }
+
+    fn logical(
+        &self,
+        logical_type: &str,
+        base: &AvroType,
+        attributes: &Map<String, Value>,
+        namespace: &str,
+    ) -> Value {
+        let format = match logical_type {
+            "date" => "date",
+            "time-millis" | "time-micros" => "time",
+            "timestamp-millis"
+            | "timestamp-micros"
+            | "timestamp-nanos"
+            | "local-timestamp-millis"
+            | "local-timestamp-micros"
+            | "local-timestamp-nanos" => "date-time",
+            "uuid" => "uuid",
+            "duration" => "duration",
+            "decimal" => {
+                let mut decimal = Map::new();
+                for key in ["precision", "scale"] {
+                    if let Some(value) = attributes.get(key) {
+                        decimal.insert(key.to_string(), value.clone());
+                    }
+                }
+                if let AvroType::Fixed { .. } = base {
+                    decimal.insert("fixed".to_string(), true.into());
+                }
+                return json!({"type": "number", "x-avro-decimal": decimal});
+            }
+            // Unknown logical types read as their base type
+            _ => return self.definition(base, namespace),
+        };
+        json!({"type": "string", "format": format})
+    }
+
+    /// A union, with `null` folded into `type` where there is a single
+    /// other branch with a plain type.
+    fn union(&self, types: &[AvroType], namespace: &str) -> Value {
+        let nullable = types.iter().any(is_null);
+        let others: Vec<&AvroType> = types.iter().filter(|t| !is_null(t)).collect();
+        match others.as_slice() {
+            [] => json!({"type": "null"}),
+            [single] if !nullable => self.schema(single, namespace),
+            [single] => {
+                let mut schema = self.schema(single, namespace);
+                match schema.get("type").cloned() {
+                    Some(Value::String(t)) if schema.get("enum").is_none() => {
+                        schema["type"] = json!([t, "null"]);
+                        schema
+                    }
+                    _ => json!({"oneOf": [{"type": "null"}, schema]}),
+                }
+            }
+            _ => {
+                let mut branches: Vec<Value> =
+                    others.iter().map(|t| self.schema(t, namespace)).collect();
+                if nullable {
+                    branches.insert(0, json!({"type": "null"}));
+                }
+                json!({"oneOf": branches})
+            }
+        }
+    }
+
+    /// The property schema of a record field, and whether it is optional:
+    /// a nullable union is an optional property of its other branches.
+    fn field(&self, field: &AvroField, namespace: &str) -> (Value, bool) {
+        let (mut schema, optional) = match &field.field_type {
+            AvroType::Union(types) if types.iter().any(is_null) => {
+                let others: Vec<AvroType> = types.iter().filter(|t| !is_null(t)).cloned().collect();
+                let schema = match others.as_slice() {
+                    [single] => self.schema(single, namespace),
+                    _ => self.union(&others, namespace),
+                };
+                (schema, true)
+            }
+            other => (self.schema(other, namespace), false),
+        };
+        if let Value::Object(obj) = &mut schema {
+            if let Some(doc) = &field.doc {
+                obj.insert("description".to_string(), doc.clone().into());
+            }
+            if let Some(default) = field.default.as_ref().filter(|d| !d.is_null()) {
+                obj.insert("default".to_string(), default.clone());
+            }
+            if let Some(value) = field.attributes.get("const") {
+                obj.insert("const".to_string(), value.clone());
+            }
+        }
+        (schema, optional)
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(feature = "cli")]
+use avrotize::avro::{validate, AvroType};
+#[cfg(feature = "cli")]
+use avrotize::avro2jsonschema::avro_to_jsons;
+#[cfg(feature = "cli")]
+use avrotize::error::Error;
+#[cfg(feature = "cli")]
+use clap::Parser;
+
+#[cfg(feature = "cli")]
+#[derive(Parser)]
+#[command(name = "avro2jsonschema", about = "Convert Avro Schema to JSON Schema")]
+struct Cli {
+    /// Path to the Avro schema (`.avsc`) input
+    #[arg(value_name = "AVRO")]
+    input: String,
+
+    /// Path to the JSON Schema output
+    #[arg(value_name = "JSONSCHEMA")]
+    output: String,
+}
+
+#[cfg(feature = "cli")]
+fn convert(input: &str, output: &str) -> Result<(), Error> {
+    let content = std::fs::read_to_string(input).map_err(|source| Error::Io {
+        path: input.to_string(),
+        source,
+    })?;
+    let schema: AvroType = serde_json::from_str(&content).map_err(|source| Error::Json {
+        uri: input.to_string(),
+        source,
+    })?;
+    validate(&schema)?;
+    let json_schema = serde_json::to_string_pretty(&avro_to_jsons(&schema)).unwrap();
+    std::fs::write(output, json_schema).map_err(|source| Error::Io {
+        path: output.to_string(),
+        source,
+    })
+}
+
+#[cfg(feature = "cli")]
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = convert(&cli.input, &cli.output) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "cli"))]
+fn main() {
+    eprintln!("This binary is only available with the `cli` feature enabled.");
+    std::process::exit(1);
+}
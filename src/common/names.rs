@@ -54,6 +54,17 @@ pub fn avro_namespace(name: &str) -> String {
     val
 }
 
+/// Whether `name` is a valid Avro name (`[A-Za-z_][A-Za-z0-9_]*`).
+pub fn is_avro_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether `namespace` is empty or a dot-separated sequence of valid Avro names.
+pub fn is_avro_namespace(namespace: &str) -> bool {
+    namespace.is_empty() || namespace.split('.').all(is_avro_name)
+}
+
 /// Convert string to PascalCase.
 pub fn pascal(input: &str) -> String {
     if input.contains("::") {
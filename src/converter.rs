@@ -1,214 +1,537 @@
 //! Conversion from JSON Schema to Avro schema.
 //!
-//! Provides the main entry points [`jsons_to_avro`] and
-//! [`convert_jsons_to_avro`], which can be used programmatically
-//! or through the CLI.
+//! The main entry point is a [`JsonToAvroConverter`], configured with
+//! [`JsonToAvroConverter::builder`], whose `convert_value`, `convert_file`
+//! and `convert_url` methods convert one schema each while sharing the
+//! [`references::ContentCache`] used for external `$ref`s. The free functions
+//! [`jsons_to_avro`] and [`convert_jsons_to_avro`] convert a single schema
+//! with a default converter.
 pub mod analysis;
+pub mod builder;
 pub mod composition;
 pub mod conversion;
 pub mod definitions;
+pub mod diagnostics;
 pub mod emptiness;
+pub mod mapping;
 pub mod merging;
+pub mod options;
 pub mod postprocess;
 pub mod references;
+pub mod resources;
 pub mod state;
 pub mod structs;
 pub mod types;
 pub mod unions;
 pub mod utils;
 
+pub use builder::JsonToAvroConverterBuilder;
 pub use state::JsonToAvroConverter;
 
+use conversion::{definition_namespace, definition_to_avro_type};
 use definitions::process_definition;
+use diagnostics::Diagnostic;
+use mapping::Mapping;
 use postprocess::postprocess_schema;
+use references::{base_url, fetch_content};
+use resources::Location;
 use utils::id_to_avro_namespace;
 
 use serde_json::Value;
 use std::fs;
 use std::path::Path;
-use url::Url;
 
+use crate::avro::{to_idl, validate, validate_documents, AvroType, Fingerprints};
+use crate::common::names::{avro_name, avro_namespace, is_avro_name, is_avro_namespace, pascal};
 use crate::common::traversal::find_schema_node;
 use crate::dependency_resolver::{inline_dependencies_of, sort_messages_by_dependencies};
+use crate::error::Error;
 
-#[cfg_attr(feature = "trace", crustrace::instrument)]
-/// Convert an in-memory JSON Schema into an Avro Schema.
-///
-/// This handles definitions, root objects, and dependency resolution.
-/// Returns either a single Avro schema object or a list of schemas.
-pub fn jsons_to_avro(
-    json_schema: &Value,
-    namespace: &str,
-    utility_namespace: &str,
-    base_uri: &str,
-    split_top_level: bool,
-) -> Value {
-    let mut avro_schema: Vec<Value> = Vec::new();
-    let mut record_stack: Vec<String> = Vec::new();
-
-    let _url = Url::parse(base_uri).unwrap_or_else(|_| Url::parse("file:///tmp").unwrap());
-    let mut root_name = "document".to_string();
-    let mut root_namespace = namespace.to_string();
-
-    // definitions / $defs
-    if let Some(defs) = json_schema
-        .get("definitions")
-        .or_else(|| json_schema.get("$defs"))
-    {
-        if let Some(map) = defs.as_object() {
-            for (def_name, schema) in map {
-                if schema.is_object() {
-                    process_definition(
-                        json_schema,
-                        namespace,
-                        utility_namespace,
-                        base_uri,
-                        &mut avro_schema,
-                        &mut record_stack,
-                        def_name,
-                        schema,
-                        false,
-                    );
-                }
+/// An Avro schema converted from JSON Schema, with the diagnostics raised
+/// while converting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub schema: AvroType,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Where the schema's named types and fields came from, and the original
+    /// names of those that were renamed.
+    pub mapping: Mapping,
+}
+
+impl JsonToAvroConverter {
+    #[cfg_attr(feature = "trace", crustrace::instrument)]
+    /// Convert an in-memory JSON Schema into an Avro Schema.
+    ///
+    /// This handles definitions, root objects, and dependency resolution.
+    /// The schema is either a single Avro type or, for several top-level
+    /// types, an [`AvroType::Union`] listing them in dependency order; it is
+    /// returned with the [`Diagnostic`]s for anything that was approximated.
+    ///
+    /// Fails if a reference cannot be resolved, a construct cannot be
+    /// expressed in Avro, or the result is not a valid Avro schema (see
+    /// [`validate()`]).
+    pub fn jsons_to_avro(
+        &mut self,
+        json_schema: &Value,
+        namespace: &str,
+        utility_namespace: &str,
+        base_uri: &str,
+        split_top_level: bool,
+    ) -> Result<Conversion, Error> {
+        for ns in [namespace, utility_namespace] {
+            if !is_avro_namespace(ns) {
+                return Err(Error::InvalidName {
+                    name: ns.to_string(),
+                });
             }
         }
-    }
-
-    // Root
-    if json_schema.is_object() {
-        if let Some((ns, name)) = process_definition(
+        if let Some(name) = self.root_class_name.as_deref().filter(|n| !is_avro_name(n)) {
+            return Err(Error::InvalidName {
+                name: name.to_string(),
+            });
+        }
+        self.error = None;
+        self.diagnostics.take();
+        let mut avro_schema = self.jsons_to_avro_value(
             json_schema,
             namespace,
             utility_namespace,
             base_uri,
-            &mut avro_schema,
-            &mut record_stack,
-            &root_name,
+            split_top_level,
+        );
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let mapping = mapping::extract(&mut avro_schema, &self.root_base_uri);
+        let avro_schema =
+            AvroType::try_from(avro_schema).map_err(|message| Error::InvalidSchema { message })?;
+        match &avro_schema {
+            // Split types are written to separate files
+            AvroType::Union(types) if split_top_level => validate_documents(types)?,
+            _ => validate(&avro_schema)?,
+        }
+        Ok(Conversion {
+            schema: avro_schema,
+            diagnostics: self.diagnostics.take(),
+            mapping,
+        })
+    }
+
+    /// Name of the root type: the configured root class name, else the
+    /// schema's `title` in PascalCase, else the file stem of `base_uri`.
+    fn root_name(&self, json_schema: &Value, base_uri: &str) -> String {
+        if let Some(name) = &self.root_class_name {
+            return name.clone();
+        }
+        if let Some(title) = json_schema.get("title").and_then(|t| t.as_str()) {
+            return avro_name(&pascal(title));
+        }
+        Path::new(base_uri)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .filter(|stem| !stem.is_empty())
+            .map(avro_name)
+            .unwrap_or_else(|| "document".to_string())
+    }
+
+    #[cfg_attr(feature = "trace", crustrace::instrument)]
+    /// Convert an in-memory JSON Schema into the Avro schema JSON the typed
+    /// model is read from.
+    fn jsons_to_avro_value(
+        &mut self,
+        json_schema: &Value,
+        namespace: &str,
+        utility_namespace: &str,
+        base_uri: &str,
+        split_top_level: bool,
+    ) -> Value {
+        let mut avro_schema: Vec<Value> = Vec::new();
+        let mut record_stack: Vec<String> = Vec::new();
+
+        let retrieval_uri = base_url(base_uri);
+        self.resources
+            .add_document(&retrieval_uri, json_schema.clone());
+        self.root_base_uri = retrieval_uri.to_string();
+        // Definitions resolve against the root's `$id` when it declares one
+        let root_location = Location {
+            document: self.root_base_uri.clone(),
+            pointer: String::new(),
+        };
+        let root_id = self
+            .resources
+            .base_of(&root_location)
+            .map(|(uri, _)| uri.to_string())
+            .unwrap_or_else(|| self.root_base_uri.clone());
+        self.root_namespace = namespace.to_string();
+        self.imported_types.clear();
+        self.inline_refs.clear();
+        self.open_records.clear();
+        self.pending_location = None;
+        self.dynamic_scope = vec![root_id.clone()];
+        let mut root_name = self.root_name(json_schema, base_uri);
+        let mut root_namespace = namespace.to_string();
+        self.diagnostics.jump(root_location.clone());
+
+        // definitions / $defs, converted once each and shared with `$ref`s to them
+        for keyword in ["definitions", "$defs"] {
+            if let Some(map) = json_schema.get(keyword).and_then(|d| d.as_object()) {
+                for (def_name, schema) in map {
+                    if schema.is_object() {
+                        let location = Location {
+                            document: self.root_base_uri.clone(),
+                            pointer: format!(
+                                "/{keyword}/{}",
+                                def_name.replace('~', "~0").replace('/', "~1")
+                            ),
+                        };
+                        definition_to_avro_type(
+                            self,
+                            &location,
+                            def_name,
+                            &definition_namespace(schema, namespace, &root_id),
+                            utility_namespace,
+                            &root_id,
+                            &mut avro_schema,
+                            &mut Vec::new(),
+                        );
+                    }
+                }
+            }
+        }
+
+        // Root
+        if json_schema.is_object() {
+            self.pending_location = Some(root_location.to_string());
+            if let Some((ns, name)) = process_definition(
+                self,
+                json_schema,
+                namespace,
+                utility_namespace,
+                base_uri,
+                &mut avro_schema,
+                &mut record_stack,
+                &root_name,
+                json_schema,
+                true,
+            ) {
+                root_namespace = ns;
+                root_name = name;
+            }
+        }
+
+        // Postprocess unmerged types
+        postprocess_schema(&mut avro_schema, Vec::new());
+
+        // Inline or sort
+        if split_top_level {
+            Value::Array(
+                avro_schema
+                    .into_iter()
+                    .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("record"))
+                    .collect(),
+            )
+        } else if !avro_schema.is_empty() {
+            if json_schema.get("definitions").is_none() && json_schema.get("$defs").is_none() {
+                let mut recursion_stack = Vec::new();
+                if let Some(root) = find_schema_node(
+                    &|t: &Value| {
+                        t.get("name").and_then(|n| n.as_str()) == Some(&root_name)
+                            && t.get("namespace").and_then(|n| n.as_str()) == Some(&root_namespace)
+                    },
+                    &Value::Array(avro_schema.clone()),
+                    &mut recursion_stack,
+                ) {
+                    let mut root_copy = root.clone();
+                    inline_dependencies_of(&mut avro_schema.clone(), &mut root_copy);
+                    return root_copy;
+                }
+            }
+            Value::Array(sort_messages_by_dependencies(
+                &mut avro_schema.clone(),
+                &mut self.diagnostics,
+            ))
+        } else {
+            Value::Array(Vec::new())
+        }
+    }
+
+    #[cfg_attr(feature = "trace", crustrace::instrument)]
+    /// Convert an in-memory JSON Schema, retrieved from `base_uri`.
+    ///
+    /// Relative `$ref`s resolve against `base_uri`. Types go in the
+    /// converter's namespace, else one derived from the schema's `$id`, else
+    /// from the file name in `base_uri`.
+    pub fn convert_value(
+        &mut self,
+        json_schema: &Value,
+        base_uri: &str,
+    ) -> Result<Conversion, Error> {
+        let namespace = self.namespace.clone();
+        let utility_namespace = self.utility_namespace.clone();
+        self.convert_with_namespaces(
             json_schema,
-            true,
-        ) {
-            root_namespace = ns;
-            root_name = name;
+            base_uri,
+            namespace.as_deref(),
+            utility_namespace.as_deref(),
+            self.split_top_level_records,
+        )
+    }
+
+    #[cfg_attr(feature = "trace", crustrace::instrument)]
+    /// Convert the JSON Schema file at `path`; see [`Self::convert_value`].
+    pub fn convert_file(
+        &mut self,
+        path: impl AsRef<Path> + std::fmt::Debug,
+    ) -> Result<Conversion, Error> {
+        let path = path.as_ref().display().to_string();
+        self.convert_url(&path)
+    }
+
+    #[cfg_attr(feature = "trace", crustrace::instrument)]
+    /// Convert the JSON Schema at `url` (`http(s)://`, `file://` or a path),
+    /// fetched through the converter's content cache; see [`Self::convert_value`].
+    pub fn convert_url(&mut self, url: &str) -> Result<Conversion, Error> {
+        let json_schema = self.load(url)?;
+        self.convert_value(&json_schema, url)
+    }
+
+    /// Fetch and parse the JSON document at `url`.
+    fn load(&mut self, url: &str) -> Result<Value, Error> {
+        let content = fetch_content(url, &mut self.content_cache)?;
+        serde_json::from_str(&content).map_err(|source| Error::Json {
+            uri: url.to_string(),
+            source,
+        })
+    }
+
+    /// Convert with the given namespace overrides, deriving the rest.
+    fn convert_with_namespaces(
+        &mut self,
+        json_schema: &Value,
+        base_uri: &str,
+        namespace: Option<&str>,
+        utility_namespace: Option<&str>,
+        split_top_level: bool,
+    ) -> Result<Conversion, Error> {
+        let mut ns: String = namespace.map(|s| s.to_string()).unwrap_or_else(|| {
+            Path::new(base_uri)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(avro_namespace)
+                .unwrap_or_default()
+        });
+
+        if let Some(id) = json_schema.get("$id").and_then(|v| v.as_str()) {
+            let id_ns = id_to_avro_namespace(id);
+            if !id_ns.is_empty() {
+                ns = id_ns;
+            }
         }
+
+        let utility_ns = if let Some(u) = utility_namespace {
+            u.to_string()
+        } else {
+            format!("{ns}.utility")
+        };
+
+        self.jsons_to_avro(json_schema, &ns, &utility_ns, base_uri, split_top_level)
     }
 
-    // Postprocess unmerged types
-    postprocess_schema(&mut avro_schema, Vec::new());
+    #[cfg_attr(feature = "trace", crustrace::instrument)]
+    /// Convert JSON Schema file into Avro Schema file(s).
+    ///
+    /// This reads a JSON Schema file (from disk or HTTP), converts it to Avro,
+    /// and writes the `.avsc` file(s) to the given path.
+    ///
+    /// # Arguments
+    /// * `json_schema_file_path` - Path or URL of the input JSON Schema.
+    /// * `avro_schema_path` - Path where the Avro schema file(s) will be written.
+    /// * `namespace` - Optional namespace override.
+    /// * `utility_namespace` - Optional namespace for utility types.
+    /// * `root_class_name` - Optional name for the root record type.
+    /// * `split_top_level_records` - If true, write each top-level record to a separate file.
+    ///
+    /// # Returns
+    /// Returns the [`Diagnostic`]s of the conversion on success, or the
+    /// [`Error`] that stopped it.
+    pub fn convert_jsons_to_avro(
+        &mut self,
+        json_schema_file_path: &str,
+        avro_schema_path: &str,
+        namespace: Option<&str>,
+        utility_namespace: Option<&str>,
+        root_class_name: Option<&str>,
+        split_top_level_records: bool,
+    ) -> Result<Vec<Diagnostic>, Error> {
+        let json_schema = self.load(json_schema_file_path)?;
+        let namespace = namespace
+            .map(str::to_string)
+            .or_else(|| self.namespace.clone());
+        let utility_namespace = utility_namespace
+            .map(str::to_string)
+            .or_else(|| self.utility_namespace.clone());
+        let split_top_level_records = split_top_level_records || self.split_top_level_records;
 
-    // Inline or sort
-    if split_top_level {
-        Value::Array(
-            avro_schema
-                .into_iter()
-                .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("record"))
-                .collect(),
+        let configured = root_class_name.map(|name| self.root_class_name.replace(name.to_string()));
+        let conversion = self.convert_with_namespaces(
+            &json_schema,
+            json_schema_file_path,
+            namespace.as_deref(),
+            utility_namespace.as_deref(),
+            split_top_level_records,
+        );
+        if let Some(configured) = configured {
+            self.root_class_name = configured;
+        }
+        let conversion = conversion?;
+        write_avro(
+            &conversion.schema,
+            avro_schema_path,
+            split_top_level_records,
+        )?;
+        Ok(conversion.diagnostics)
+    }
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Write an Avro schema as pretty-printed `.avsc` JSON to `path`.
+///
+/// With `split`, `path` is a directory and each named type of a top-level
+/// list is written to its own `<name>.avsc` inside it.
+pub fn write_avro(avro_schema: &AvroType, path: &str, split: bool) -> Result<(), Error> {
+    if split {
+        if let AvroType::Union(types) = avro_schema {
+            for item in types {
+                if let Some(name) = item.name() {
+                    let file_path = Path::new(path).join(format!("{name}.avsc"));
+                    fs::write(&file_path, serde_json::to_string_pretty(item).unwrap()).map_err(
+                        |source| Error::Io {
+                            path: file_path.display().to_string(),
+                            source,
+                        },
+                    )?;
+                }
+            }
+        }
+    } else {
+        fs::write(path, serde_json::to_string_pretty(avro_schema).unwrap()).map_err(|source| {
+            Error::Io {
+                path: path.to_string(),
+                source,
+            }
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Write an Avro schema as Avro IDL to `path`, wrapped in a `protocol` if
+/// one is named; see [`to_idl`].
+pub fn write_idl(avro_schema: &AvroType, path: &str, protocol: Option<&str>) -> Result<(), Error> {
+    fs::write(path, to_idl(avro_schema, protocol)).map_err(|source| Error::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Write the [`Fingerprints`] of an Avro schema next to its `.avsc` output.
+///
+/// Mirrors [`write_avro`]: `path` is the `.avsc` file, written alongside as
+/// `<stem>.fingerprints.json`, or with `split` the directory in which each
+/// `<name>.avsc` gets a `<name>.fingerprints.json`.
+pub fn write_fingerprints(avro_schema: &AvroType, path: &str, split: bool) -> Result<(), Error> {
+    let write = |file_path: &Path, schema: &AvroType| {
+        let fingerprints = Fingerprints::of(schema);
+        fs::write(
+            file_path,
+            serde_json::to_string_pretty(&fingerprints).unwrap(),
         )
-    } else if !avro_schema.is_empty() {
-        if json_schema.get("definitions").is_none() && json_schema.get("$defs").is_none() {
-            let mut recursion_stack = Vec::new();
-            if let Some(root) = find_schema_node(
-                &|t: &Value| {
-                    t.get("name").and_then(|n| n.as_str()) == Some(&root_name)
-                        && t.get("namespace").and_then(|n| n.as_str()) == Some(&root_namespace)
-                },
-                &Value::Array(avro_schema.clone()),
-                &mut recursion_stack,
-            ) {
-                let mut root_copy = root.clone();
-                inline_dependencies_of(&mut avro_schema.clone(), &mut root_copy);
-                return root_copy;
+        .map_err(|source| Error::Io {
+            path: file_path.display().to_string(),
+            source,
+        })
+    };
+    if split {
+        if let AvroType::Union(types) = avro_schema {
+            for item in types {
+                if let Some(name) = item.name() {
+                    write(
+                        &Path::new(path).join(format!("{name}.fingerprints.json")),
+                        item,
+                    )?;
+                }
             }
         }
-        Value::Array(sort_messages_by_dependencies(&mut avro_schema.clone()))
+        Ok(())
     } else {
-        Value::Array(Vec::new())
+        write(
+            &Path::new(path).with_extension("fingerprints.json"),
+            avro_schema,
+        )
     }
 }
 
 #[cfg_attr(feature = "trace", crustrace::instrument)]
-/// Convert JSON Schema file into Avro Schema file(s).
+/// Write the [`Mapping`] of a conversion next to its `.avsc` output.
 ///
-/// This reads a JSON Schema file (from disk or HTTP), converts it to Avro,
-/// and writes the `.avsc` file(s) to the given path.
+/// Like [`write_fingerprints`], `path` is the `.avsc` file, written alongside
+/// as `<stem>.mapping.json`, or with `split` the directory that gets a
+/// `mapping.json` covering all of its schemas.
+pub fn write_mapping(mapping: &Mapping, path: &str, split: bool) -> Result<(), Error> {
+    let file_path = if split {
+        Path::new(path).join("mapping.json")
+    } else {
+        Path::new(path).with_extension("mapping.json")
+    };
+    fs::write(&file_path, serde_json::to_string_pretty(mapping).unwrap()).map_err(|source| {
+        Error::Io {
+            path: file_path.display().to_string(),
+            source,
+        }
+    })
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Convert an in-memory JSON Schema into an Avro Schema.
 ///
-/// # Arguments
-/// * `json_schema_file_path` - Path or URL of the input JSON Schema.
-/// * `avro_schema_path` - Path where the Avro schema file(s) will be written.
-/// * `namespace` - Optional namespace override.
-/// * `utility_namespace` - Optional namespace for utility types.
-/// * `root_class_name` - Optional name for the root record type.
-/// * `split_top_level_records` - If true, write each top-level record to a separate file.
+/// Uses a default [`JsonToAvroConverter`]; see
+/// [`JsonToAvroConverter::jsons_to_avro`].
+pub fn jsons_to_avro(
+    json_schema: &Value,
+    namespace: &str,
+    utility_namespace: &str,
+    base_uri: &str,
+    split_top_level: bool,
+) -> Result<Conversion, Error> {
+    JsonToAvroConverter::new().jsons_to_avro(
+        json_schema,
+        namespace,
+        utility_namespace,
+        base_uri,
+        split_top_level,
+    )
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Convert JSON Schema file into Avro Schema file(s).
 ///
-/// # Returns
-/// Returns `Ok(())` on success, or an error string if conversion failed.
+/// Uses a default [`JsonToAvroConverter`]; see
+/// [`JsonToAvroConverter::convert_jsons_to_avro`].
 pub fn convert_jsons_to_avro(
     json_schema_file_path: &str,
     avro_schema_path: &str,
     namespace: Option<&str>,
     utility_namespace: Option<&str>,
-    _root_class_name: Option<&str>,
+    root_class_name: Option<&str>,
     split_top_level_records: bool,
-) -> Result<(), String> {
-    let content = if json_schema_file_path.starts_with("http") {
-        reqwest::blocking::get(json_schema_file_path)
-            .map_err(|e| format!("HTTP fetch failed: {e}"))?
-            .text()
-            .map_err(|e| format!("Invalid response body: {e}"))?
-    } else {
-        fs::read_to_string(json_schema_file_path)
-            .map_err(|e| format!("Failed to read schema file: {e}"))?
-    };
-
-    let json_schema: Value =
-        serde_json::from_str(&content).map_err(|e| format!("Invalid JSON schema: {e}"))?;
-
-    let mut ns: String = namespace.map(|s| s.to_string()).unwrap_or_else(|| {
-        Path::new(json_schema_file_path)
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string()
-    });
-
-    if let Some(id) = json_schema.get("$id").and_then(|v| v.as_str()) {
-        let id_ns = id_to_avro_namespace(id);
-        if !id_ns.is_empty() {
-            ns = id_ns;
-        }
-    }
-
-    let utility_ns = if let Some(u) = utility_namespace {
-        u.to_string()
-    } else {
-        format!("{ns}.utility")
-    };
-
-    let avro_schema = jsons_to_avro(
-        &json_schema,
-        &ns,
-        &utility_ns,
+) -> Result<Vec<Diagnostic>, Error> {
+    JsonToAvroConverter::new().convert_jsons_to_avro(
         json_schema_file_path,
+        avro_schema_path,
+        namespace,
+        utility_namespace,
+        root_class_name,
         split_top_level_records,
-    );
-
-    if split_top_level_records {
-        if let Some(arr) = avro_schema.as_array() {
-            for item in arr {
-                if let Some(name) = item.get("name").and_then(|n| n.as_str()) {
-                    let file_path = Path::new(avro_schema_path).join(format!("{name}.avsc"));
-                    fs::write(&file_path, serde_json::to_string_pretty(item).unwrap())
-                        .map_err(|e| format!("Failed to write {file_path:?}: {e}"))?;
-                }
-            }
-        }
-    } else {
-        fs::write(
-            avro_schema_path,
-            serde_json::to_string_pretty(&avro_schema).unwrap(),
-        )
-        .map_err(|e| format!("Failed to write {avro_schema_path}: {e}"))?;
-    }
-
-    Ok(())
+    )
 }
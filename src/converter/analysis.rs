@@ -41,6 +41,14 @@ pub fn has_composition_keywords(json_object: &Value) -> bool {
             || json_object.get("anyOf").is_some())
 }
 
+/// Check if a JSON object is a reference: `$ref`, `$dynamicRef` or `$recursiveRef`.
+pub fn has_reference_keyword(json_object: &Value) -> bool {
+    json_object.is_object()
+        && (json_object.get("$ref").is_some()
+            || json_object.get("$dynamicRef").is_some()
+            || json_object.get("$recursiveRef").is_some())
+}
+
 /// Check if a JSON object is an enum.
 pub fn has_enum_keyword(json_object: &Value) -> bool {
     json_object.is_object() && json_object.get("enum").is_some()
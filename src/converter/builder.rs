@@ -0,0 +1,96 @@
+use crate::converter::options::ConverterOptions;
+use crate::converter::references::{Catalog, ContentCache};
+use crate::converter::state::JsonToAvroConverter;
+
+/// Builder for a configured [`JsonToAvroConverter`].
+///
+/// ```
+/// use avrotize::converter::JsonToAvroConverter;
+///
+/// let converter = JsonToAvroConverter::builder()
+///     .namespace("com.example")
+///     .max_recursion_depth(20)
+///     .offline(true)
+///     .build();
+/// ```
+#[derive(Debug, Default)]
+pub struct JsonToAvroConverterBuilder {
+    namespace: Option<String>,
+    utility_namespace: Option<String>,
+    root_class_name: Option<String>,
+    split_top_level_records: bool,
+    max_recursion_depth: Option<usize>,
+    options: ConverterOptions,
+    catalog: Catalog,
+    offline: bool,
+}
+
+impl JsonToAvroConverterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Namespace for converted types, instead of one derived from the
+    /// schema's `$id` or the source's file name.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Namespace for utility types, instead of `<namespace>.utility`.
+    pub fn utility_namespace(mut self, utility_namespace: impl Into<String>) -> Self {
+        self.utility_namespace = Some(utility_namespace.into());
+        self
+    }
+
+    /// Name of the root type, instead of the schema's `title` (in PascalCase)
+    /// or the source's file stem.
+    pub fn root_class_name(mut self, name: impl Into<String>) -> Self {
+        self.root_class_name = Some(name.into());
+        self
+    }
+
+    /// Return the top-level records as a list instead of one nested schema.
+    pub fn split_top_level_records(mut self, split: bool) -> Self {
+        self.split_top_level_records = split;
+        self
+    }
+
+    /// Nesting depth beyond which subschemas become a generic type (default 40).
+    pub fn max_recursion_depth(mut self, depth: usize) -> Self {
+        self.max_recursion_depth = Some(depth);
+        self
+    }
+
+    /// How JSON Schema formats and keywords map onto Avro types.
+    pub fn options(mut self, options: ConverterOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Local copies of remote documents, consulted before the network.
+    pub fn catalog(mut self, catalog: Catalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// Never fetch remote documents that the catalog does not cover.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn build(self) -> JsonToAvroConverter {
+        let mut converter = JsonToAvroConverter::new();
+        converter.namespace = self.namespace;
+        converter.utility_namespace = self.utility_namespace;
+        converter.root_class_name = self.root_class_name;
+        converter.split_top_level_records = self.split_top_level_records;
+        if let Some(depth) = self.max_recursion_depth {
+            converter.max_recursion_depth = depth;
+        }
+        converter.options = self.options;
+        converter.content_cache = ContentCache::with_catalog(self.catalog, self.offline);
+        converter
+    }
+}
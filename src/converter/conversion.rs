@@ -1,19 +1,502 @@
 #[cfg_attr(feature = "trace", crustrace::omni)]
 mod innermod {
     use crate::common::generic::generic_type;
-    use crate::common::names::{avro_name, pascal};
-    use crate::converter::analysis::{has_composition_keywords, has_enum_keyword, is_array_object};
+    use crate::common::names::{avro_name, avro_name_with_altname, pascal};
+    use crate::converter::analysis::{
+        has_composition_keywords, has_enum_keyword, has_reference_keyword, is_array_object,
+    };
+    use crate::converter::definitions::process_definition;
+    use crate::converter::mapping::{annotate, annotate_source};
     use crate::converter::merging::{merge_avro_schemas, merge_json_schemas};
+    use crate::converter::references::{base_url, fetch_content};
+    use crate::converter::resources::{rebase, Location};
+    use crate::converter::state::JsonToAvroConverter;
     use crate::converter::structs::{
-        create_array_type, create_avro_record, create_enum_type, create_map_type,
-        create_wrapper_record,
+        create_array_type, create_avro_record, create_enum_of_values, create_enum_type,
+        create_map_type, create_wrapper_record, nullable, set_altname,
+    };
+    use crate::converter::types::{
+        fit_integer_to_bounds, json_schema_constrained_primitive_to_avro_type,
+        json_schema_primitive_to_avro_type,
     };
-    use crate::converter::types::json_schema_primitive_to_avro_type;
-    use crate::converter::utils::{merge_dependencies_into_parent, merge_description_into_doc};
+    use crate::converter::utils::{
+        id_to_avro_namespace, merge_dependencies_into_parent, merge_description_into_doc,
+    };
+    use crate::error::Error;
     use serde_json::{json, Value};
+    use url::Url;
+
+    /// Locate the target of a `$ref`, loading and indexing external documents
+    /// (relative files, `file://` or `http(s)://` URLs) through the converter's
+    /// content cache on first use.
+    fn locate_ref(
+        converter: &mut JsonToAvroConverter,
+        ref_str: &str,
+        base_uri: &str,
+    ) -> Result<Location, Error> {
+        let unresolvable = |message: String| Error::UnresolvableRef {
+            reference: ref_str.to_string(),
+            message,
+        };
+        let url = base_url(base_uri)
+            .join(ref_str)
+            .map_err(|e| unresolvable(format!("invalid URI: {e}")))?;
+        if !converter.resources.has_resource(&url) {
+            let mut doc_url = url.clone();
+            doc_url.set_fragment(None);
+            let text = fetch_content(doc_url.as_str(), &mut converter.content_cache)?;
+            let doc: Value = serde_json::from_str(&text).map_err(|source| Error::Json {
+                uri: doc_url.to_string(),
+                source,
+            })?;
+            converter.resources.add_document(&doc_url, doc);
+        }
+        converter
+            .resources
+            .locate(&url)
+            .ok_or_else(|| unresolvable(format!("no subschema at {url}")))
+    }
+
+    /// Locate the target of a `$ref`, `$dynamicRef` or `$recursiveRef`.
+    ///
+    /// A `$dynamicRef` whose initial target declares the matching
+    /// `$dynamicAnchor` resolves instead to the outermost resource in the
+    /// dynamic scope that declares that anchor. Likewise a `$recursiveRef`
+    /// whose target has `$recursiveAnchor: true` resolves to the outermost
+    /// resource with a recursive anchor.
+    fn locate_reference(
+        converter: &mut JsonToAvroConverter,
+        json_type: &Value,
+        base_uri: &str,
+    ) -> Result<Location, Error> {
+        if let Some(ref_str) = json_type.get("$ref").and_then(|r| r.as_str()) {
+            return locate_ref(converter, ref_str, base_uri);
+        }
+        if let Some(ref_str) = json_type.get("$dynamicRef").and_then(|r| r.as_str()) {
+            let location = locate_ref(converter, ref_str, base_uri)?;
+            let Some(name) = ref_str
+                .split_once('#')
+                .map(|(_, fragment)| fragment)
+                .filter(|f| !f.is_empty() && !f.starts_with('/'))
+            else {
+                return Ok(location);
+            };
+            let is_dynamic = converter
+                .resources
+                .subschema(&location)
+                .and_then(|s| s.get("$dynamicAnchor").cloned())
+                .is_some_and(|anchor| anchor == name);
+            if !is_dynamic {
+                return Ok(location);
+            }
+            let dynamic = converter
+                .dynamic_scope
+                .iter()
+                .find_map(|resource| converter.resources.dynamic_anchor(resource, name));
+            return Ok(dynamic.unwrap_or(location));
+        }
+        if let Some(ref_str) = json_type.get("$recursiveRef").and_then(|r| r.as_str()) {
+            let location = locate_ref(converter, ref_str, base_uri)?;
+            let is_recursive = converter
+                .resources
+                .subschema(&location)
+                .and_then(|s| s.get("$recursiveAnchor").and_then(|a| a.as_bool()))
+                == Some(true);
+            if !is_recursive {
+                return Ok(location);
+            }
+            let outermost = converter
+                .dynamic_scope
+                .iter()
+                .find(|resource| converter.resources.has_recursive_anchor(resource))
+                .and_then(|resource| Url::parse(resource).ok())
+                .and_then(|uri| converter.resources.locate(&uri));
+            return Ok(outermost.unwrap_or(location));
+        }
+        Err(Error::UnresolvableRef {
+            reference: String::new(),
+            message: "missing $ref".to_string(),
+        })
+    }
+
+    /// Name of a root-level `$defs`/`definitions` entry addressed by `pointer`.
+    fn definition_name(pointer: &str) -> Option<String> {
+        let rest = pointer
+            .strip_prefix("/$defs/")
+            .or_else(|| pointer.strip_prefix("/definitions/"))?;
+        (!rest.contains('/')).then(|| rest.replace("~1", "/").replace("~0", "~"))
+    }
+
+    /// Convert the target of a `$ref` into an Avro type.
+    ///
+    /// - Subschemas of the root document's own resource are converted in place,
+    ///   except `$defs`/`definitions` entries, which become named types;
+    /// - subschemas of external documents or of embedded resources (with their
+    ///   own `$id`) become named types in the namespace derived from that `$id`.
+    ///
+    /// Named types are converted once and referred to by full name thereafter.
+    fn ref_to_avro_type(
+        converter: &mut JsonToAvroConverter,
+        json_type: &Value,
+        record_name: &str,
+        field_name: &str,
+        namespace: &str,
+        utility_namespace: &str,
+        dependencies: &mut Vec<String>,
+        base_uri: &str,
+        avro_schema: &mut Vec<Value>,
+        record_stack: &mut Vec<String>,
+    ) -> Value {
+        let ref_str = ["$ref", "$dynamicRef", "$recursiveRef"]
+            .iter()
+            .find_map(|keyword| json_type.get(*keyword).and_then(|r| r.as_str()))
+            .unwrap_or("");
+        let location = match locate_reference(converter, json_type, base_uri) {
+            Ok(location) => location,
+            Err(e) => {
+                converter.fail(e);
+                return json!("string"); // placeholder
+            }
+        };
+        let (Some(subschema), Some(doc), Some((resource, explicit_id))) = (
+            converter.resources.subschema(&location),
+            converter.resources.document(&location.document),
+            converter.resources.base_of(&location),
+        ) else {
+            converter.fail(Error::UnresolvableRef {
+                reference: ref_str.to_string(),
+                message: format!("no subschema at {location}"),
+            });
+            return json!("string"); // placeholder
+        };
+        converter.dynamic_scope.push(resource.to_string());
+        let referrer = converter.diagnostics.jump(location.clone());
+        let avro_type = located_ref_to_avro_type(
+            converter,
+            ref_str,
+            &location,
+            &subschema,
+            &doc,
+            &resource,
+            explicit_id,
+            record_name,
+            field_name,
+            namespace,
+            utility_namespace,
+            dependencies,
+            avro_schema,
+            record_stack,
+        );
+        converter.diagnostics.jump(referrer);
+        converter.dynamic_scope.pop();
+        avro_type
+    }
+
+    /// Convert the located target of a reference; see [`ref_to_avro_type`].
+    fn located_ref_to_avro_type(
+        converter: &mut JsonToAvroConverter,
+        ref_str: &str,
+        location: &Location,
+        subschema: &Value,
+        doc: &Value,
+        resource: &Url,
+        explicit_id: bool,
+        record_name: &str,
+        field_name: &str,
+        namespace: &str,
+        utility_namespace: &str,
+        dependencies: &mut Vec<String>,
+        avro_schema: &mut Vec<Value>,
+        record_stack: &mut Vec<String>,
+    ) -> Value {
+        let parent_base = converter
+            .resources
+            .base_above(location)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| location.document.clone());
+        let in_root_document = location.document == converter.root_base_uri;
+
+        // A reference back to a record still being converted is a recursive type
+        if let Some(full_name) = open_record(converter, &location.to_string()) {
+            dependencies.push(full_name.clone());
+            return json!(full_name);
+        }
+
+        if in_root_document {
+            if let Some(def_name) = definition_name(&location.pointer) {
+                let def_namespace =
+                    definition_namespace(subschema, &converter.root_namespace, &parent_base);
+                return definition_to_avro_type(
+                    converter,
+                    location,
+                    &def_name,
+                    &def_namespace,
+                    utility_namespace,
+                    &parent_base,
+                    avro_schema,
+                    dependencies,
+                );
+            }
+        }
+
+        // Only definitions, resource roots and objects make sensible named types;
+        // anything else (e.g. an array deep inside another resource) is inlined
+        let is_object = subschema.get("properties").is_some()
+            || subschema.get("type").and_then(|t| t.as_str()) == Some("object");
+        let is_named_target = definition_name(&location.pointer).is_some()
+            || converter.resources.locate(resource).as_ref() == Some(location);
+        if (in_root_document && converter.resources.is_document_root(resource))
+            || (!is_named_target && !is_object)
+        {
+            let key = location.to_string();
+            if converter.inline_refs.contains(&key) {
+                converter.fail(Error::UnsupportedKeyword {
+                    keyword: "$ref".to_string(),
+                    message: format!(
+                        "{ref_str} recurses through a non-record type, which Avro cannot express"
+                    ),
+                });
+                return json!("string"); // placeholder
+            }
+            converter.inline_refs.push(key.clone());
+            let avro_type = if is_object {
+                converter.pending_location = Some(key);
+                let (local_name, local_altname) =
+                    avro_name_with_altname(if field_name.is_empty() {
+                        record_name
+                    } else {
+                        field_name
+                    });
+                let mut avro_record = json_schema_object_to_avro_record(
+                    converter,
+                    &local_name,
+                    subschema,
+                    namespace,
+                    utility_namespace,
+                    doc,
+                    &parent_base,
+                    avro_schema,
+                    record_stack,
+                );
+                keep_altname(&mut avro_record, &local_name, local_altname.as_deref());
+                avro_record
+            } else {
+                json_type_to_avro_type(
+                    converter,
+                    subschema,
+                    record_name,
+                    field_name,
+                    namespace,
+                    utility_namespace,
+                    dependencies,
+                    doc,
+                    &parent_base,
+                    avro_schema,
+                    record_stack,
+                    1,
+                )
+            };
+            converter.inline_refs.pop();
+            return avro_type;
+        }
+
+        let type_namespace = if explicit_id {
+            Some(id_to_avro_namespace(resource.as_str())).filter(|ns| !ns.is_empty())
+        } else {
+            None
+        }
+        .unwrap_or_else(|| namespace.to_string());
+        let type_name = definition_name(&location.pointer)
+            .or_else(|| {
+                location
+                    .pointer
+                    .rsplit('/')
+                    .find(|seg| !seg.is_empty())
+                    .map(str::to_string)
+            })
+            .or_else(|| {
+                subschema
+                    .get("title")
+                    .and_then(|t| t.as_str())
+                    .map(str::to_string)
+            })
+            .or_else(|| {
+                resource
+                    .path_segments()
+                    .and_then(|mut segs| segs.next_back())
+                    .map(|file| file.split('.').next().unwrap_or(file).to_string())
+            })
+            .unwrap_or_default();
+        definition_to_avro_type(
+            converter,
+            location,
+            &type_name,
+            &type_namespace,
+            utility_namespace,
+            &parent_base,
+            avro_schema,
+            dependencies,
+        )
+    }
+
+    /// Full name of the record being converted from the subschema at `location`.
+    fn open_record(converter: &JsonToAvroConverter, location: &str) -> Option<String> {
+        converter
+            .open_records
+            .iter()
+            .rev()
+            .find(|(open, _)| open == location)
+            .map(|(_, full_name)| full_name.clone())
+    }
+
+    /// Namespace for a subschema whose `$id` makes it an embedded resource.
+    ///
+    /// Document roots keep the namespace they were given.
+    fn embedded_id_namespace(
+        converter: &JsonToAvroConverter,
+        parent_base_uri: &str,
+        base_uri: &str,
+    ) -> Option<String> {
+        if base_uri == parent_base_uri || converter.resources.is_document_root(&base_url(base_uri))
+        {
+            return None;
+        }
+        Some(id_to_avro_namespace(base_uri)).filter(|ns| !ns.is_empty())
+    }
+
+    /// Namespace for a definition: derived from its own `$id` (resolved against
+    /// `base_uri`) if it has one.
+    pub fn definition_namespace(schema: &Value, namespace: &str, base_uri: &str) -> String {
+        schema
+            .get("$id")
+            .and_then(|id| id.as_str())
+            .filter(|id| !id.starts_with('#'))
+            .map(|_| id_to_avro_namespace(&rebase(base_uri, schema)))
+            .filter(|ns| !ns.is_empty())
+            .unwrap_or_else(|| namespace.to_string())
+    }
+
+    /// Convert the subschema at `location` into a named type, once.
+    ///
+    /// The full name is remembered against the location, so later references
+    /// (including cyclic ones, which see a provisional name) resolve to it.
+    /// Subschemas that do not yield a named type are converted inline instead.
+    pub fn definition_to_avro_type(
+        converter: &mut JsonToAvroConverter,
+        location: &Location,
+        type_name: &str,
+        namespace: &str,
+        utility_namespace: &str,
+        base_uri: &str,
+        avro_schema: &mut Vec<Value>,
+        dependencies: &mut Vec<String>,
+    ) -> Value {
+        let key = location.to_string();
+        if let Some(full_name) = open_record(converter, &key) {
+            dependencies.push(full_name.clone());
+            return json!(full_name);
+        }
+        if let Some(full_name) = converter.imported_types.get(&key) {
+            dependencies.push(full_name.clone());
+            return json!(full_name);
+        }
+        let (Some(subschema), Some(doc)) = (
+            converter.resources.subschema(location),
+            converter.resources.document(&location.document),
+        ) else {
+            converter.fail(Error::UnresolvableRef {
+                reference: type_name.to_string(),
+                message: format!("no subschema at {location}"),
+            });
+            return json!("string"); // placeholder
+        };
+
+        // Register the name up front so that cyclic references terminate
+        converter
+            .imported_types
+            .insert(key.clone(), format!("{namespace}.{}", avro_name(type_name)));
+
+        // The definition's resource joins the dynamic scope while it converts
+        let resource = converter
+            .resources
+            .base_of(location)
+            .map(|(uri, _)| uri.to_string());
+        converter.dynamic_scope.extend(resource.clone());
+        let referrer = converter.diagnostics.jump(location.clone());
+        let avro_type = named_definition_to_avro_type(
+            converter,
+            &key,
+            &subschema,
+            &doc,
+            type_name,
+            namespace,
+            utility_namespace,
+            base_uri,
+            avro_schema,
+            dependencies,
+        );
+        converter.diagnostics.jump(referrer);
+        if resource.is_some() {
+            converter.dynamic_scope.pop();
+        }
+        avro_type
+    }
+
+    /// Convert a definition registered under `key`; see [`definition_to_avro_type`].
+    fn named_definition_to_avro_type(
+        converter: &mut JsonToAvroConverter,
+        key: &str,
+        subschema: &Value,
+        doc: &Value,
+        type_name: &str,
+        namespace: &str,
+        utility_namespace: &str,
+        base_uri: &str,
+        avro_schema: &mut Vec<Value>,
+        dependencies: &mut Vec<String>,
+    ) -> Value {
+        converter.pending_location = Some(key.to_string());
+        if let Some((ns, name)) = process_definition(
+            converter,
+            doc,
+            namespace,
+            utility_namespace,
+            base_uri,
+            avro_schema,
+            &mut Vec::new(),
+            type_name,
+            subschema,
+            false,
+        ) {
+            let full_name = format!("{ns}.{name}");
+            converter
+                .imported_types
+                .insert(key.to_string(), full_name.clone());
+            dependencies.push(full_name.clone());
+            return json!(full_name);
+        }
+
+        // Unnamed types (primitives, arrays, maps) are inlined at the use site
+        converter.imported_types.remove(key);
+        json_type_to_avro_type(
+            converter,
+            subschema,
+            type_name,
+            "",
+            namespace,
+            utility_namespace,
+            dependencies,
+            doc,
+            base_uri,
+            avro_schema,
+            &mut Vec::new(),
+            1,
+        )
+    }
 
     /// Handle `patternProperties` in a JSON Schema object.
     fn handle_pattern_properties(
+        converter: &mut JsonToAvroConverter,
         json_object: &Value,
         record_name: &str,
         namespace: &str,
@@ -29,9 +512,12 @@ mod innermod {
             .get("patternProperties")
             .and_then(|pp| pp.as_object())
         {
+            converter.diagnostics.enter("patternProperties");
             for (pattern, prop_schema) in pattern_props {
                 let mut deps = Vec::new();
+                converter.diagnostics.enter(pattern);
                 let avro_type = json_type_to_avro_type(
+                    converter,
                     prop_schema,
                     record_name,
                     pattern,
@@ -44,9 +530,11 @@ mod innermod {
                     record_stack,
                     1,
                 );
+                converter.diagnostics.leave();
                 extension_types.push(avro_type);
                 dependencies.extend(deps);
             }
+            converter.diagnostics.leave();
         }
 
         extension_types
@@ -54,19 +542,25 @@ mod innermod {
 
     /// Handle `additionalProperties` in a JSON Schema object.
     fn handle_additional_properties(
+        converter: &mut JsonToAvroConverter,
         json_object: &Value,
         record_name: &str,
         namespace: &str,
         utility_namespace: &str,
         base_uri: &str,
         avro_schema: &mut Vec<Value>,
-        record_stack: &mut Vec<String>,
+        record_stack: &[String],
         dependencies: &mut Vec<String>,
     ) -> Option<Value> {
         if let Some(additional) = json_object.get("additionalProperties") {
             if additional.is_boolean() {
                 if additional.as_bool().unwrap() {
                     // "additionalProperties": true -> generic map<string, any>
+                    converter.diagnostics.warn(
+                        "any-type",
+                        Some("additionalProperties"),
+                        format!("additional properties of {record_name} are untyped; using string"),
+                    );
                     return Some(json!({
                         "type": "map",
                         "values": "string"
@@ -76,10 +570,12 @@ mod innermod {
                 let mut deps = Vec::new();
 
                 // Use parent namespace only: drop the current record from the stack
-                let mut parent_stack = record_stack.clone();
+                let mut parent_stack = record_stack.to_vec();
                 parent_stack.pop();
 
+                converter.diagnostics.enter("additionalProperties");
                 let avro_type = json_type_to_avro_type(
+                    converter,
                     additional,
                     record_name,
                     &(record_name.to_string() + "_extensions"),
@@ -92,6 +588,7 @@ mod innermod {
                     &mut parent_stack,
                     1,
                 );
+                converter.diagnostics.leave();
                 dependencies.extend(deps);
                 return Some(json!({
                     "type": "map",
@@ -102,8 +599,22 @@ mod innermod {
         None
     }
 
+    /// Record `original` as the `altnames` of a record or enum that was named
+    /// `name` after normalizing it.
+    fn keep_altname(avro_type: &mut Value, name: &str, original: Option<&str>) {
+        let is_named = matches!(
+            avro_type.get("type").and_then(Value::as_str),
+            Some("record" | "enum")
+        ) && avro_type.get("name").and_then(Value::as_str) == Some(name)
+            && avro_type.get("altnames").is_none();
+        if let Some(original) = original.filter(|_| is_named) {
+            set_altname(avro_type, original);
+        }
+    }
+
     /// Convert a JSON schema object declaration to an Avro record.
     pub fn json_schema_object_to_avro_record(
+        converter: &mut JsonToAvroConverter,
         name: &str,
         json_object: &Value,
         namespace: &str,
@@ -117,39 +628,34 @@ mod innermod {
             return Value::Array(vec![]);
         }
         let mut dependencies: Vec<String> = Vec::new();
+        let location = converter.pending_location.take();
 
-        if let Some(ref_str) = json_object.get("$ref").and_then(|r| r.as_str()) {
-            if let Some(def_name) = ref_str.strip_prefix("#/$defs/") {
-                // 👉 Just return the Avro type name that was registered by process_definition
-                let fq_name = format!("{}.{}", namespace, def_name);
-                return json!(fq_name);
-            }
-
-            if let Some(ptr) = ref_str.strip_prefix('#') {
-                if let Some(resolved) = json_schema.pointer(ptr) {
-                    return json_schema_object_to_avro_record(
-                        name,
-                        resolved,
-                        namespace,
-                        utility_namespace,
-                        json_schema,
-                        base_uri,
-                        avro_schema,
-                        record_stack,
-                    );
-                }
-            }
+        // An embedded `$id` changes the base URI and names the namespace
+        let parent_base_uri = base_uri;
+        let rebased_uri = rebase(base_uri, json_object);
+        let base_uri = rebased_uri.as_str();
+        let id_namespace = embedded_id_namespace(converter, parent_base_uri, base_uri);
+        let namespace = id_namespace.as_deref().unwrap_or(namespace);
 
-            eprintln!(
-                "WARN: external $ref resolution not yet implemented: {}",
-                ref_str
+        if has_reference_keyword(json_object) {
+            return ref_to_avro_type(
+                converter,
+                json_object,
+                name,
+                "",
+                namespace,
+                utility_namespace,
+                &mut dependencies,
+                base_uri,
+                avro_schema,
+                record_stack,
             );
-            return json!("string"); // placeholder
         }
 
         // Composition keywords: allOf, oneOf, anyOf
         if has_composition_keywords(json_object) {
             let t = json_type_to_avro_type(
+                converter,
                 json_object,
                 name,
                 "",
@@ -157,7 +663,7 @@ mod innermod {
                 utility_namespace,
                 &mut dependencies,
                 json_schema,
-                base_uri,
+                parent_base_uri,
                 avro_schema,
                 record_stack,
                 1,
@@ -182,6 +688,7 @@ mod innermod {
             } else {
                 t
             };
+            annotate_source(&mut avro_type, converter.diagnostics.location());
             // Merge dependencies from the wrapped inner type into the wrapper record itself
             if avro_type.get("fields").is_some() {
                 // Move the whole fields array out
@@ -211,11 +718,14 @@ mod innermod {
         // Enum
         if has_enum_keyword(json_object) {
             if let Some(enum_vals) = json_object.get("enum").and_then(|v| v.as_array()) {
-                let symbols: Vec<String> = enum_vals
-                    .iter()
-                    .filter_map(|v| v.as_str().map(avro_name))
-                    .collect();
-                let mut avro_enum = create_enum_type(&pascal(name), namespace, &symbols);
+                let mut avro_enum = create_enum_of_values(
+                    &pascal(name),
+                    namespace,
+                    enum_vals,
+                    converter.options.enum_values,
+                )
+                .unwrap_or_else(|| create_enum_type(&pascal(name), namespace, &[]));
+                annotate_source(&mut avro_enum, converter.diagnostics.location());
                 merge_description_into_doc(json_object, &mut avro_enum);
                 return avro_enum;
             }
@@ -228,12 +738,13 @@ mod innermod {
         } else {
             title.unwrap_or_default() // convert nulls to empty string, avro_name will turn it into "_"
         };
-        let record_name = avro_name(raw_name);
+        let (record_name, record_altname) = avro_name_with_altname(raw_name);
 
         // Arrays
         if is_array_object(json_object) {
             let mut deps = Vec::new();
             let mut array_type = json_type_to_avro_type(
+                converter,
                 json_object,
                 name,
                 &record_name,
@@ -241,7 +752,7 @@ mod innermod {
                 utility_namespace,
                 &mut deps,
                 json_schema,
-                base_uri,
+                parent_base_uri,
                 avro_schema,
                 record_stack,
                 1,
@@ -264,6 +775,7 @@ mod innermod {
                 array_type,
             );
             merge_description_into_doc(json_object, &mut avro_array);
+            annotate_source(&mut avro_array, converter.diagnostics.location());
             if avro_array.get("items").is_some() {
                 // Move it out
                 let mut items_val = avro_array["items"].take();
@@ -278,7 +790,8 @@ mod innermod {
         }
 
         // Adjust namespace if nested (based on parent, not current)
-        let effective_namespace = if let Some(parent) = record_stack.last() {
+        let effective_namespace = if let (None, Some(parent)) = (&id_namespace, record_stack.last())
+        {
             crate::converter::utils::compose_namespace(&[namespace, &format!("{}_types", parent)])
         } else {
             namespace.to_string()
@@ -286,8 +799,20 @@ mod innermod {
 
         // (IMPORTANT: NO EARLY RETURNS MUST FOLLOW THIS WITHOUT POP)
         record_stack.push(record_name.clone());
+        let full_name = if effective_namespace.is_empty() {
+            record_name.clone()
+        } else {
+            format!("{effective_namespace}.{record_name}")
+        };
+        converter
+            .open_records
+            .push((location.unwrap_or_default(), full_name));
 
         let mut avro_record = create_avro_record(&record_name, &effective_namespace, Vec::new());
+        annotate_source(&mut avro_record, converter.diagnostics.location());
+        if let Some(original) = &record_altname {
+            set_altname(&mut avro_record, original);
+        }
 
         // Collect "required" list from the parent object
         let required_fields: Vec<&str> = json_object
@@ -298,7 +823,10 @@ mod innermod {
 
         // Handle fields
         if let Some(props) = json_object.get("properties").and_then(|p| p.as_object()) {
+            converter.diagnostics.enter("properties");
             for (field_name, field_schema) in props {
+                converter.diagnostics.enter(field_name);
+                let source = converter.diagnostics.location().clone();
                 // Normalize: wrap single object as a one-element array
                 let schema_list: Vec<&Value> = if field_schema.is_array() {
                     field_schema.as_array().unwrap().iter().collect()
@@ -330,65 +858,85 @@ mod innermod {
                     }
 
                     // Special case $ref
-                    let avro_field_type =
-                        if let Some(ref_str) = schema_obj.get("$ref").and_then(|r| r.as_str()) {
-                            if let Some(def_name) = ref_str.strip_prefix("#/$defs/") {
-                                json!(format!("{}.{}", effective_namespace, def_name))
-                            } else if let Some(ptr) = ref_str.strip_prefix('#') {
-                                if let Some(resolved) = json_schema.pointer(ptr) {
-                                    json_schema_object_to_avro_record(
-                                        field_name,
-                                        resolved,
-                                        &effective_namespace,
-                                        utility_namespace,
-                                        json_schema,
-                                        base_uri,
-                                        avro_schema,
-                                        record_stack,
-                                    )
-                                } else {
-                                    json!("string")
-                                }
-                            } else {
-                                eprintln!("WARN: external $ref not supported: {}", ref_str);
-                                json!("string")
-                            }
-                        } else {
-                            json_type_to_avro_type(
-                                schema_obj,
-                                &record_name,
-                                field_name,
-                                &effective_namespace,
-                                utility_namespace,
-                                &mut deps,
-                                json_schema,
-                                base_uri,
-                                avro_schema,
-                                record_stack,
-                                1,
-                            )
-                        };
+                    let avro_field_type = if has_reference_keyword(schema_obj) {
+                        ref_to_avro_type(
+                            converter,
+                            schema_obj,
+                            &record_name,
+                            field_name,
+                            &effective_namespace,
+                            utility_namespace,
+                            &mut deps,
+                            base_uri,
+                            avro_schema,
+                            record_stack,
+                        )
+                    } else {
+                        json_type_to_avro_type(
+                            converter,
+                            schema_obj,
+                            &record_name,
+                            field_name,
+                            &effective_namespace,
+                            utility_namespace,
+                            &mut deps,
+                            json_schema,
+                            base_uri,
+                            avro_schema,
+                            record_stack,
+                            1,
+                        )
+                    };
 
                     last_avro_type = Some(avro_field_type);
                 }
 
                 // Pick last type seen (or fallback)
-                let mut effective_type = last_avro_type.unwrap_or(json!("string"));
+                let mut effective_type = last_avro_type.unwrap_or_else(|| {
+                    converter.diagnostics.warn(
+                        "unhandled-type",
+                        None,
+                        format!("property {field_name} has no schema object; using string"),
+                    );
+                    json!("string")
+                });
+                converter.diagnostics.leave();
 
                 // Nullable if not required
-                if !required_fields.contains(&field_name.as_str()) {
-                    match &effective_type {
+                let required = required_fields.contains(&field_name.as_str());
+                if !required {
+                    match &mut effective_type {
                         Value::Array(arr) if arr.iter().any(|t| t == "null") => {}
+                        Value::Array(arr) => arr.insert(0, json!("null")),
                         _ => {
                             effective_type = json!(["null", effective_type]);
                         }
                     }
                 }
+                // A union's default must match its first branch
+                if let (Some(d), Value::Array(branches)) = (&default_val, &mut effective_type) {
+                    if let Some(i) = branches.iter().position(|t| t == "null") {
+                        let null = branches.remove(i);
+                        if d.is_null() {
+                            branches.insert(0, null);
+                        } else {
+                            branches.push(null);
+                        }
+                    }
+                }
 
+                let (avro_field_name, original) = avro_name_with_altname(field_name);
                 let mut field = json!({
-                    "name": field_name,
+                    "name": avro_field_name,
                     "type": effective_type
                 });
+                annotate_source(&mut field, &source);
+                if let Some(original) = original {
+                    set_altname(&mut field, &original);
+                }
+                if required {
+                    annotate(&mut field, "required", Value::Bool(true));
+                }
                 if let Some(c) = const_val {
                     field["const"] = c;
                 }
@@ -402,10 +950,12 @@ mod innermod {
                 avro_record["fields"].as_array_mut().unwrap().push(field);
                 dependencies.extend(deps);
             }
+            converter.diagnostics.leave();
         }
 
         // Handle extensions: patternProperties & additionalProperties
         let pattern_types = handle_pattern_properties(
+            converter,
             json_object,
             &record_name,
             &effective_namespace,
@@ -428,6 +978,7 @@ mod innermod {
         }
 
         if let Some(additional) = handle_additional_properties(
+            converter,
             json_object,
             &record_name,
             &effective_namespace,
@@ -445,6 +996,7 @@ mod innermod {
             if !has_fields {
                 // Case: only `additionalProperties` → return the map itself.
                 record_stack.pop();
+                converter.open_records.pop();
                 return additional;
             }
 
@@ -465,12 +1017,14 @@ mod innermod {
         }
 
         record_stack.pop();
+        converter.open_records.pop();
 
         avro_record
     }
 
     /// Convert a JSON Schema type into an Avro type.
     pub fn json_type_to_avro_type(
+        converter: &mut JsonToAvroConverter,
         json_type: &Value,
         record_name: &str,
         field_name: &str,
@@ -483,41 +1037,90 @@ mod innermod {
         record_stack: &mut Vec<String>,
         recursion_depth: usize,
     ) -> Value {
-        if recursion_depth >= 40 {
-            eprintln!(
-                "WARNING: Maximum recursion depth reached for {record_name} at field {field_name}"
+        if recursion_depth >= converter.max_recursion_depth {
+            converter.diagnostics.warn(
+                "max-depth",
+                None,
+                format!(
+                    "maximum recursion depth reached for {record_name} at field {field_name}; using a generic type"
+                ),
             );
             return serde_json::Value::Array(generic_type());
         }
 
-        let local_name = avro_name(if !field_name.is_empty() {
+        let (local_name, local_altname) = avro_name_with_altname(if !field_name.is_empty() {
             field_name
         } else {
             record_name
         });
         let avro_type = Value::Null;
 
+        let parent_base_uri = base_uri;
+        let rebased_uri = rebase(base_uri, json_type);
+        let base_uri = rebased_uri.as_str();
+
         if let Some(obj) = json_type.as_object() {
+            if has_reference_keyword(json_type) {
+                return ref_to_avro_type(
+                    converter,
+                    json_type,
+                    record_name,
+                    field_name,
+                    namespace,
+                    utility_namespace,
+                    dependencies,
+                    base_uri,
+                    avro_schema,
+                    record_stack,
+                );
+            }
+
             let mut json_object_type = obj.get("type").cloned();
 
             // Handle list-of-types (e.g. "type": ["null","string"])
             if let Some(Value::Array(type_list)) = &json_object_type {
                 if type_list.len() == 1 {
                     json_object_type = Some(type_list[0].clone());
-                } else if type_list.len() == 2 && type_list.iter().any(|t| t == "null") {
-                    let other = type_list.iter().find(|t| *t != "null").unwrap().clone();
-                    json_object_type = Some(other);
-                } else {
-                    let mut one_of = vec![];
-                    for t in type_list {
-                        if t != "null" {
-                            one_of.push(json!({ "type": t }));
+                } else if type_list.iter().any(|t| t == "null") {
+                    // Convert the other types, then let null through even if
+                    // the property is required
+                    let others: Vec<Value> =
+                        type_list.iter().filter(|t| *t != "null").cloned().collect();
+                    let mut new_obj = obj.clone();
+                    new_obj.insert(
+                        "type".to_string(),
+                        match &others[..] {
+                            [other] => other.clone(),
+                            _ => Value::Array(others),
+                        },
+                    );
+                    let avro_type = json_type_to_avro_type(
+                        converter,
+                        &Value::Object(new_obj),
+                        record_name,
+                        field_name,
+                        namespace,
+                        utility_namespace,
+                        dependencies,
+                        json_schema,
+                        parent_base_uri,
+                        avro_schema,
+                        record_stack,
+                        recursion_depth + 1,
+                    );
+                    return match avro_type {
+                        Value::Array(ref branches) if branches.iter().any(|t| t == "null") => {
+                            avro_type
                         }
-                    }
+                        _ => nullable(avro_type),
+                    };
+                } else {
+                    let one_of = type_list.iter().map(|t| json!({ "type": t })).collect();
                     let mut new_obj = obj.clone();
                     new_obj.remove("type");
                     new_obj.insert("oneOf".to_string(), Value::Array(one_of));
                     return json_type_to_avro_type(
+                        converter,
                         &Value::Object(new_obj),
                         record_name,
                         field_name,
@@ -525,7 +1128,7 @@ mod innermod {
                         utility_namespace,
                         dependencies,
                         json_schema,
-                        base_uri,
+                        parent_base_uri,
                         avro_schema,
                         record_stack,
                         recursion_depth + 1,
@@ -534,14 +1137,16 @@ mod innermod {
             }
 
             // Handle compositions
-            if let Some(subs) = obj
-                .get("oneOf")
-                .and_then(|v| v.as_array())
-                .or_else(|| obj.get("anyOf").and_then(|v| v.as_array()))
+            if let Some((keyword, subs)) = ["oneOf", "anyOf"]
+                .into_iter()
+                .find_map(|keyword| Some((keyword, obj.get(keyword)?.as_array()?)))
             {
                 let mut union_types = Vec::new();
-                for sub in subs {
+                converter.diagnostics.enter(keyword);
+                for (i, sub) in subs.iter().enumerate() {
+                    converter.diagnostics.enter(&i.to_string());
                     let avro_ty = json_type_to_avro_type(
+                        converter,
                         sub,
                         record_name,
                         field_name,
@@ -554,17 +1159,20 @@ mod innermod {
                         record_stack,
                         recursion_depth + 1,
                     );
+                    converter.diagnostics.leave();
                     match avro_ty {
                         Value::Array(mut arr) => union_types.append(&mut arr),
                         other => union_types.push(other),
                     }
                 }
+                converter.diagnostics.leave();
                 return Value::Array(union_types);
             }
 
             if obj.contains_key("allOf") {
                 let merged = merge_json_schemas(std::slice::from_ref(json_type), false);
                 return json_type_to_avro_type(
+                    converter,
                     &merged,
                     record_name,
                     field_name,
@@ -572,7 +1180,7 @@ mod innermod {
                     utility_namespace,
                     dependencies,
                     json_schema,
-                    base_uri,
+                    parent_base_uri,
                     avro_schema,
                     record_stack,
                     recursion_depth + 1,
@@ -581,16 +1189,15 @@ mod innermod {
 
             // Handle enums
             if let Some(enum_vals) = obj.get("enum").and_then(|v| v.as_array()) {
-                let symbols: Vec<String> = enum_vals
-                    .iter()
-                    .filter_map(|v| v.as_str().map(avro_name))
-                    .collect();
-                if !symbols.is_empty() {
-                    return create_enum_type(
-                        &local_name,
-                        &format!("{namespace}.{record_name}_types"),
-                        &symbols,
-                    );
+                if let Some(mut avro_enum) = create_enum_of_values(
+                    &local_name,
+                    &format!("{namespace}.{record_name}_types"),
+                    enum_vals,
+                    converter.options.enum_values,
+                ) {
+                    annotate_source(&mut avro_enum, converter.diagnostics.location());
+                    keep_altname(&mut avro_enum, &local_name, local_altname.as_deref());
+                    return avro_enum;
                 }
             }
 
@@ -603,7 +1210,9 @@ mod innermod {
                         items.clone()
                     } else {
                         // homogeneous array → recurse
-                        json_type_to_avro_type(
+                        converter.diagnostics.enter("items");
+                        let item_type = json_type_to_avro_type(
+                            converter,
                             items,
                             record_name,
                             field_name,
@@ -615,11 +1224,18 @@ mod innermod {
                             avro_schema,
                             record_stack,
                             recursion_depth + 1,
-                        )
+                        );
+                        converter.diagnostics.leave();
+                        item_type
                     };
                     dependencies.extend(deps);
                     return create_array_type(item_type);
                 } else {
+                    converter.diagnostics.warn(
+                        "any-type",
+                        None,
+                        format!("items of {local_name} are untyped; using a generic type"),
+                    );
                     return create_array_type(serde_json::Value::Array(generic_type()));
                 }
             }
@@ -631,10 +1247,17 @@ mod innermod {
                     if let Some(additional) = obj.get("additionalProperties") {
                         if additional.is_boolean() && additional.as_bool().unwrap() {
                             // any-type map
+                            converter.diagnostics.warn(
+                                "any-type",
+                                Some("additionalProperties"),
+                                format!("values of {local_name} are untyped; using a generic type"),
+                            );
                             return create_map_type(Value::Array(generic_type()), Some(field_name));
                         }
                         if additional.is_object() {
+                            converter.diagnostics.enter("additionalProperties");
                             let values_type = json_type_to_avro_type(
+                                converter,
                                 additional,
                                 record_name,
                                 &(field_name.to_string() + "_values"),
@@ -647,22 +1270,26 @@ mod innermod {
                                 record_stack,
                                 recursion_depth + 1,
                             );
+                            converter.diagnostics.leave();
                             return create_map_type(values_type, Some(field_name));
                         }
                     }
                 }
 
                 // Default: full object with properties, patternProperties, etc.
-                return json_schema_object_to_avro_record(
+                let mut avro_record = json_schema_object_to_avro_record(
+                    converter,
                     &local_name,
                     json_type,
                     namespace,
                     utility_namespace,
                     json_schema,
-                    base_uri,
+                    parent_base_uri,
                     avro_schema,
                     record_stack,
                 );
+                keep_altname(&mut avro_record, &local_name, local_altname.as_deref());
+                return avro_record;
             }
 
             // Handle const → enum
@@ -672,11 +1299,19 @@ mod innermod {
                 } else {
                     vec![c.clone()]
                 };
-                let symbols: Vec<String> = values
-                    .iter()
-                    .filter_map(|v| v.as_str().map(avro_name))
-                    .collect();
-                let mut enum_type = create_enum_type(&local_name, namespace, &symbols);
+                let mut enum_type = create_enum_of_values(
+                    &local_name,
+                    namespace,
+                    &values,
+                    converter.options.enum_values,
+                )
+                .unwrap_or_else(|| create_enum_type(&local_name, namespace, &[]));
+                if enum_type.get("type").and_then(Value::as_str) != Some("enum") {
+                    // Typed values: a primitive or union, which takes no name or doc
+                    return enum_type;
+                }
+                annotate_source(&mut enum_type, converter.diagnostics.location());
+                keep_altname(&mut enum_type, &local_name, local_altname.as_deref());
                 if let Some(desc) = obj.get("description").and_then(|d| d.as_str()) {
                     enum_type["doc"] = Value::String(desc.to_string());
                 }
@@ -691,30 +1326,39 @@ mod innermod {
 
             // Otherwise: primitives
             if let Some(Value::String(t)) = json_object_type {
+                if let Some(avro_type) = json_schema_constrained_primitive_to_avro_type(
+                    &t,
+                    json_type,
+                    record_name,
+                    field_name,
+                    namespace,
+                    &converter.options,
+                    &mut converter.diagnostics,
+                ) {
+                    return avro_type;
+                }
                 let fmt = obj.get("format").and_then(|f| f.as_str());
                 let enum_vals = obj.get("enum").and_then(|v| v.as_array());
-                let enum_strings = enum_vals.map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                        .collect::<Vec<_>>()
-                });
 
-                return json_schema_primitive_to_avro_type(
+                let avro_type = json_schema_primitive_to_avro_type(
                     &Value::String(t.clone()),
                     fmt,
-                    enum_strings
-                        .as_ref()
-                        .map(|v| {
-                            v.iter()
-                                .map(|s| Value::String(s.clone()))
-                                .collect::<Vec<_>>()
-                        })
-                        .as_deref(),
+                    enum_vals.map(|v| v.as_slice()),
                     record_name,
                     field_name,
                     namespace,
                     dependencies,
+                    &converter.options,
                 );
+                if t == "integer" {
+                    return fit_integer_to_bounds(
+                        avro_type,
+                        json_type,
+                        &local_name,
+                        &mut converter.diagnostics,
+                    );
+                }
+                return avro_type;
             }
         }
 
@@ -728,9 +1372,15 @@ mod innermod {
                 field_name,
                 namespace,
                 dependencies,
+                &converter.options,
             );
         }
 
+        converter.diagnostics.warn(
+            "any-type",
+            None,
+            format!("{local_name} is untyped; using a generic type"),
+        );
         serde_json::Value::Array(generic_type())
     }
 }
@@ -6,11 +6,16 @@ mod innermod {
     use crate::converter::conversion::json_schema_object_to_avro_record;
     use crate::converter::emptiness::is_empty_type;
     use crate::converter::postprocess::register_type;
+    use crate::converter::state::JsonToAvroConverter;
     use crate::converter::structs::create_wrapper_record;
+    use crate::converter::types::{
+        fit_integer_to_bounds, json_schema_constrained_primitive_to_avro_type,
+    };
     use crate::converter::utils::lift_dependencies_from_type;
 
     /// Process a schema definition list (e.g. `$defs` or `definitions`).
     pub fn process_definition_list(
+        converter: &mut JsonToAvroConverter,
         json_schema: &Value,
         namespace: &str,
         utility_namespace: &str,
@@ -24,6 +29,7 @@ mod innermod {
             for (sub_schema_name, schema) in map {
                 if schema.is_object() {
                     process_definition(
+                        converter,
                         json_schema,
                         namespace,
                         utility_namespace,
@@ -43,6 +49,7 @@ mod innermod {
     ///
     /// Returns `(namespace, name)` if a type was registered.
     pub fn process_definition(
+        converter: &mut JsonToAvroConverter,
         json_schema: &Value,
         namespace: &str,
         utility_namespace: &str,
@@ -68,6 +75,7 @@ mod innermod {
 
             // Now merged has no "allOf" — safe to recurse once
             return process_definition(
+                converter,
                 json_schema,
                 namespace,
                 utility_namespace,
@@ -84,6 +92,7 @@ mod innermod {
 
         let avro_schema_item_list = match ty {
             Some("object") | Some("array") => json_schema_object_to_avro_record(
+                converter,
                 schema_name,
                 schema,
                 namespace,
@@ -96,19 +105,48 @@ mod innermod {
             Some("string" | "integer" | "number" | "boolean") => {
                 let fmt = schema.get("format").and_then(|f| f.as_str());
                 let enums = schema.get("enum").and_then(|v| v.as_array());
-                crate::converter::types::json_schema_primitive_to_avro_type(
-                    &Value::String(ty.unwrap().to_string()),
-                    fmt,
-                    enums.map(|v| v.as_slice()),
+                let constrained = json_schema_constrained_primitive_to_avro_type(
+                    ty.unwrap(),
+                    schema,
                     schema_name,
                     schema_name,
                     namespace,
-                    &mut Vec::new(),
-                )
+                    &converter.options,
+                    &mut converter.diagnostics,
+                );
+                constrained.unwrap_or_else(|| {
+                    let avro_type = crate::converter::types::json_schema_primitive_to_avro_type(
+                        &Value::String(ty.unwrap().to_string()),
+                        fmt,
+                        enums.map(|v| v.as_slice()),
+                        schema_name,
+                        schema_name,
+                        namespace,
+                        &mut Vec::new(),
+                        &converter.options,
+                    );
+                    if ty == Some("integer") {
+                        fit_integer_to_bounds(
+                            avro_type,
+                            schema,
+                            schema_name,
+                            &mut converter.diagnostics,
+                        )
+                    } else {
+                        avro_type
+                    }
+                })
             }
             _ => {
-                #[cfg(feature = "trace")]
-                tracing::warn!("process_definition: unhandled type {:?}", ty);
+                let (keyword, problem) = match ty {
+                    Some(t) => (Some("type"), format!("has unhandled type {t}")),
+                    None => (None, "has no type".to_string()),
+                };
+                converter.diagnostics.warn(
+                    "unhandled-type",
+                    keyword,
+                    format!("definition {schema_name} {problem}; using string"),
+                );
                 json!("string") // safe fallback
             }
         };
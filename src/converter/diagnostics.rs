@@ -0,0 +1,120 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::converter::resources::Location;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The schema was converted faithfully; the entry is informational.
+    Info,
+    /// Part of the schema was degraded or approximated.
+    Warning,
+}
+
+/// Something noteworthy found while converting a schema.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the kind of diagnostic, e.g. `max-depth`.
+    pub code: &'static str,
+    pub message: String,
+    /// Retrieval URI of the document containing the source keyword.
+    pub document: String,
+    /// JSON Pointer to the source keyword within `document`.
+    pub pointer: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARN",
+        };
+        write!(
+            f,
+            "{severity}: {} [{}] at #{}",
+            self.message, self.code, self.pointer
+        )
+    }
+}
+
+/// Collects [`Diagnostic`]s during a conversion.
+///
+/// Tracks the location of the subschema being converted, so that entries are
+/// attributed to it: conversion [`enter`](Self::enter)s and
+/// [`leave`](Self::leave)s child keywords, and [`jump`](Self::jump)s to the
+/// targets of references.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Vec<Diagnostic>,
+    cursor: Location,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Descend into the child `segment` of the current subschema.
+    pub fn enter(&mut self, segment: &str) {
+        self.cursor.pointer.push('/');
+        self.cursor
+            .pointer
+            .push_str(&segment.replace('~', "~0").replace('/', "~1"));
+    }
+
+    /// Return to the parent of the current subschema.
+    pub fn leave(&mut self) {
+        let end = self.cursor.pointer.rfind('/').unwrap_or(0);
+        self.cursor.pointer.truncate(end);
+    }
+
+    /// Location of the subschema being converted.
+    pub fn location(&self) -> &Location {
+        &self.cursor
+    }
+
+    /// Move to `location`, returning the location to restore afterwards.
+    pub fn jump(&mut self, location: Location) -> Location {
+        std::mem::replace(&mut self.cursor, location)
+    }
+
+    /// Record a diagnostic for `keyword` of the current subschema, or for the
+    /// subschema itself when `keyword` is `None`.
+    pub fn push(
+        &mut self,
+        severity: Severity,
+        code: &'static str,
+        keyword: Option<&str>,
+        message: String,
+    ) {
+        if let Some(keyword) = keyword {
+            self.enter(keyword);
+        }
+        self.entries.push(Diagnostic {
+            severity,
+            code,
+            message,
+            document: self.cursor.document.clone(),
+            pointer: self.cursor.pointer.clone(),
+        });
+        if keyword.is_some() {
+            self.leave();
+        }
+    }
+
+    pub fn warn(&mut self, code: &'static str, keyword: Option<&str>, message: String) {
+        self.push(Severity::Warning, code, keyword, message);
+    }
+
+    pub fn info(&mut self, code: &'static str, keyword: Option<&str>, message: String) {
+        self.push(Severity::Info, code, keyword, message);
+    }
+
+    /// Take the collected diagnostics, leaving the collector empty.
+    pub fn take(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.entries)
+    }
+}
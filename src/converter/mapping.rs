@@ -0,0 +1,246 @@
+//! Where the parts of a converted schema came from in the JSON Schema.
+//!
+//! Conversion renames properties and enum values that aren't valid Avro names
+//! and wraps values that aren't records in records of their own. A
+//! [`Mapping`] records, for each named type, the subschema it was converted
+//! from, the original names of its fields and symbols, and whether it is such
+//! a wrapper, so that data can be given back its JSON shape (see
+//! [`Decoder::mapping`](crate::data::Decoder::mapping)).
+//!
+//! The original names are also kept in the schema itself, as the `altnames`
+//! of fields and types and the `altsymbols` of enums (under the purpose
+//! `"json"`). The other facts are carried under a private key while
+//! converting, from which they are moved into the mapping before the schema is
+//! returned.
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::common::names::altname;
+use crate::converter::resources::Location;
+use crate::converter::structs::ALTNAME_PURPOSE;
+
+/// Key of the facts a type or field carries for the mapping during conversion.
+pub(crate) const ANNOTATION: &str = "__mapping";
+
+/// How a converted schema's named types relate to the JSON Schema.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mapping {
+    /// Named types by full name.
+    pub types: BTreeMap<String, TypeMapping>,
+}
+
+/// The source of a record, enum or fixed type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TypeMapping {
+    /// JSON Pointer to the subschema the type was converted from, as a
+    /// fragment (`#/...`), prefixed by the document's URI for types from
+    /// other documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// For a record wrapping a value that isn't a record (a root array, a
+    /// union, a definition), the field holding the value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapper: Option<String>,
+    /// Fields of a record by Avro name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, FieldMapping>,
+    /// Original values of the enum symbols that were renamed, by symbol.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbols: BTreeMap<String, Value>,
+}
+
+/// The source of a record field.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldMapping {
+    /// Name of the property in JSON.
+    pub name: String,
+    /// JSON Pointer to the property's subschema, as for [`TypeMapping`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    /// Whether the property is `required` by its object, so a `null` is its
+    /// value rather than its absence.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+}
+
+impl Mapping {
+    /// The source of a record's field, with its JSON property name.
+    pub fn field(&self, record: &str, field: &str) -> Option<&FieldMapping> {
+        self.types.get(record)?.fields.get(field)
+    }
+
+    /// The original enum value of a symbol, if it was renamed.
+    pub fn symbol(&self, enum_name: &str, symbol: &str) -> Option<&Value> {
+        self.types.get(enum_name)?.symbols.get(symbol)
+    }
+
+    /// The JSON Pointer of an Avro path: a type's full name, or a record's
+    /// full name and a field name joined by `.`.
+    pub fn pointer(&self, avro_path: &str) -> Option<&str> {
+        if let Some(t) = self.types.get(avro_path) {
+            return t.pointer.as_deref();
+        }
+        let (record, field) = avro_path.rsplit_once('.')?;
+        self.field(record, field)?.pointer.as_deref()
+    }
+
+    /// The Avro path of a JSON Pointer, the inverse of [`Self::pointer`].
+    pub fn avro_path(&self, pointer: &str) -> Option<String> {
+        self.types.iter().find_map(|(name, t)| {
+            if t.pointer.as_deref() == Some(pointer) {
+                return Some(name.clone());
+            }
+            t.fields
+                .iter()
+                .find(|(_, f)| f.pointer.as_deref() == Some(pointer))
+                .map(|(field, _)| format!("{name}.{field}"))
+        })
+    }
+}
+
+/// Record the subschema at `location` as the source of a type or field.
+pub(crate) fn annotate_source(target: &mut Value, location: &Location) {
+    annotate(target, "pointer", Value::String(location.to_string()));
+}
+
+/// Record `fact` about a type or field, for [`extract`] to find.
+pub(crate) fn annotate(target: &mut Value, fact: &str, value: Value) {
+    if let Some(obj) = target.as_object_mut() {
+        let annotation = obj
+            .entry(ANNOTATION)
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(annotation) = annotation.as_object_mut() {
+            annotation.insert(fact.to_string(), value);
+        }
+    }
+}
+
+/// Remove the annotations from `schema`, collecting them into a [`Mapping`].
+///
+/// Pointers into `root_document` are written as bare fragments.
+pub(crate) fn extract(schema: &mut Value, root_document: &str) -> Mapping {
+    let mut mapping = Mapping::default();
+    collect(schema, "", root_document, &mut mapping);
+    strip(schema);
+    mapping
+}
+
+/// Remove annotations left where no named type or field is, e.g. on types
+/// merged into a primitive.
+fn strip(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(strip),
+        Value::Object(obj) => {
+            obj.shift_remove(ANNOTATION);
+            obj.values_mut().for_each(strip);
+        }
+        _ => {}
+    }
+}
+
+/// The annotation of a type or field, taken off it. Types merged from several
+/// sources carry a list of annotations, of which the first is used.
+fn take_annotation(obj: &mut Map<String, Value>) -> Map<String, Value> {
+    match obj.shift_remove(ANNOTATION) {
+        Some(Value::Object(annotation)) => annotation,
+        Some(Value::Array(annotations)) => annotations
+            .into_iter()
+            .find_map(|a| match a {
+                Value::Object(annotation) => Some(annotation),
+                _ => None,
+            })
+            .unwrap_or_default(),
+        _ => Map::new(),
+    }
+}
+
+fn collect(value: &mut Value, namespace: &str, root_document: &str, mapping: &mut Mapping) {
+    let relative = |pointer: Option<&Value>| {
+        let pointer = pointer?.as_str()?;
+        Some(match pointer.strip_prefix(root_document) {
+            Some(fragment) if fragment.starts_with('#') => fragment.to_string(),
+            _ => pointer.to_string(),
+        })
+    };
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| collect(item, namespace, root_document, mapping)),
+        Value::Object(obj) => {
+            let annotation = take_annotation(obj);
+            let name = obj.get("name").and_then(Value::as_str).map(str::to_string);
+            let is_named = matches!(
+                obj.get("type").and_then(Value::as_str),
+                Some("record" | "enum" | "fixed")
+            );
+            let Some(name) = name.filter(|_| is_named) else {
+                for key in ["type", "items", "values"] {
+                    if let Some(inner) = obj.get_mut(key) {
+                        collect(inner, namespace, root_document, mapping);
+                    }
+                }
+                return;
+            };
+
+            let ns = match name.rsplit_once('.') {
+                Some((ns, _)) => ns.to_string(),
+                None => obj
+                    .get("namespace")
+                    .and_then(Value::as_str)
+                    .unwrap_or(namespace)
+                    .to_string(),
+            };
+            let short = name.rsplit('.').next().unwrap_or(&name);
+            let full_name = if ns.is_empty() {
+                short.to_string()
+            } else {
+                format!("{ns}.{short}")
+            };
+            let mut type_mapping = TypeMapping {
+                pointer: relative(annotation.get("pointer")),
+                wrapper: annotation
+                    .get("wrapper")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                fields: BTreeMap::new(),
+                symbols: obj
+                    .get("altsymbols")
+                    .and_then(|a| a.get(ALTNAME_PURPOSE))
+                    .and_then(Value::as_object)
+                    .map(|symbols| {
+                        symbols
+                            .iter()
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            };
+            if let Some(fields) = obj.get_mut("fields").and_then(Value::as_array_mut) {
+                for field in fields.iter_mut() {
+                    let original = altname(field, ALTNAME_PURPOSE);
+                    let Some(field) = field.as_object_mut() else {
+                        continue;
+                    };
+                    let annotation = take_annotation(field);
+                    let Some(field_name) = field.get("name").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    type_mapping.fields.insert(
+                        field_name.to_string(),
+                        FieldMapping {
+                            name: original,
+                            pointer: relative(annotation.get("pointer")),
+                            required: annotation.get("required") == Some(&Value::Bool(true)),
+                        },
+                    );
+                    if let Some(field_type) = field.get_mut("type") {
+                        collect(field_type, &ns, root_document, mapping);
+                    }
+                }
+            }
+            mapping.types.entry(full_name).or_insert(type_mapping);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,86 @@
+//! Options controlling how JSON Schema constructs map onto Avro types.
+
+/// Precision of the Avro `timestamp-*` and `time-*` logical types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TimePrecision {
+    /// `timestamp-millis` / `time-millis`
+    #[default]
+    Millis,
+    /// `timestamp-micros` / `time-micros`
+    Micros,
+}
+
+/// How `enum` and `const` values that aren't all strings are converted.
+///
+/// String values always become the symbols of an Avro enum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum EnumValues {
+    /// The values' own type (`int`, `long`, `double`, `boolean`, `null`)
+    /// listing them in an `"enum"` attribute, or a union of these (with an
+    /// enum of the strings) for values of different types
+    #[default]
+    Typed,
+    /// An Avro enum with a symbol per value, mapped back to the values by its
+    /// `altsymbols`
+    Symbols,
+}
+
+/// Options for [`JsonToAvroConverter`](crate::converter::JsonToAvroConverter).
+#[derive(Debug, Clone)]
+pub struct ConverterOptions {
+    /// Precision used for `date-time` and `time` formats.
+    pub time_precision: TimePrecision,
+    /// Map `date-time` to `local-timestamp-*` (no time zone) instead of `timestamp-*`.
+    pub local_timestamps: bool,
+    /// Decimal precision when the schema gives no bounds to derive it from.
+    pub decimal_precision: u32,
+    /// Decimal scale for `"x-avro-decimal": true` without a `multipleOf`.
+    pub decimal_scale: u32,
+    /// Back decimals with a `fixed` of the smallest sufficient size instead of `bytes`.
+    pub decimal_fixed: bool,
+    /// Representation of `enum` and `const` values that aren't all strings.
+    pub enum_values: EnumValues,
+}
+
+impl Default for ConverterOptions {
+    fn default() -> Self {
+        Self {
+            time_precision: TimePrecision::default(),
+            local_timestamps: false,
+            decimal_precision: 38,
+            decimal_scale: 9,
+            decimal_fixed: false,
+            enum_values: EnumValues::default(),
+        }
+    }
+}
+
+impl ConverterOptions {
+    /// Avro type for a JSON Schema `date-time`.
+    pub fn timestamp_type(&self) -> serde_json::Value {
+        let prefix = if self.local_timestamps {
+            "local-timestamp"
+        } else {
+            "timestamp"
+        };
+        let logical_type = match self.time_precision {
+            TimePrecision::Millis => format!("{prefix}-millis"),
+            TimePrecision::Micros => format!("{prefix}-micros"),
+        };
+        serde_json::json!({"type": "long", "logicalType": logical_type})
+    }
+
+    /// Avro type for a JSON Schema `time`.
+    pub fn time_type(&self) -> serde_json::Value {
+        match self.time_precision {
+            TimePrecision::Millis => {
+                serde_json::json!({"type": "int", "logicalType": "time-millis"})
+            }
+            TimePrecision::Micros => {
+                serde_json::json!({"type": "long", "logicalType": "time-micros"})
+            }
+        }
+    }
+}
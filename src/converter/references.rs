@@ -2,12 +2,71 @@ use reqwest::blocking::Client;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
+use std::path::PathBuf;
 use std::time::Duration;
 use url::Url;
 
+use crate::error::Error;
+
+/// Maps remote URI prefixes to local directories or bundled documents.
+///
+/// A URI matching a prefix is looked up under the mapped path: if the path is
+/// a directory the rest of the URI is joined onto it, otherwise the path is
+/// taken to be the document itself. The longest matching prefix wins.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    entries: Vec<(String, PathBuf)>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map URIs starting with `prefix` to `path`.
+    pub fn add(&mut self, prefix: &str, path: impl Into<PathBuf>) {
+        self.entries.push((prefix.to_string(), path.into()));
+    }
+
+    /// Add a mapping given as `PREFIX=PATH`, as accepted by the CLI.
+    pub fn add_mapping(&mut self, mapping: &str) -> Result<(), String> {
+        let (prefix, path) = mapping
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid catalog mapping (expected PREFIX=PATH): {mapping}"))?;
+        self.add(prefix, path);
+        Ok(())
+    }
+
+    /// Find the local path for `uri`, if any prefix matches.
+    pub fn lookup(&self, uri: &str) -> Option<PathBuf> {
+        let (prefix, path) = self
+            .entries
+            .iter()
+            .filter(|(prefix, _)| uri.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())?;
+        let rest = uri[prefix.len()..].trim_start_matches('/');
+        if path.is_dir() {
+            Some(path.join(rest))
+        } else {
+            Some(path.clone())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
 /// A simple cache for fetched schema content.
+///
+/// Lookups consult the cache first, then the [`Catalog`], and only then the
+/// network, unless the cache is `offline`.
+#[derive(Debug)]
 pub struct ContentCache {
     cache: HashMap<String, String>,
+    pub catalog: Catalog,
+    /// Refuse to fetch `http(s)` URIs that are not in the catalog.
+    pub offline: bool,
 }
 
 impl Default for ContentCache {
@@ -20,6 +79,17 @@ impl ContentCache {
     pub fn new() -> Self {
         Self {
             cache: HashMap::new(),
+            catalog: Catalog::new(),
+            offline: false,
+        }
+    }
+
+    /// Create a cache that resolves URIs through `catalog`.
+    pub fn with_catalog(catalog: Catalog, offline: bool) -> Self {
+        Self {
+            cache: HashMap::new(),
+            catalog,
+            offline,
         }
     }
 
@@ -32,34 +102,74 @@ impl ContentCache {
     }
 }
 
+/// Interpret a base URI that may also be a plain filesystem path.
+///
+/// Relative paths are made absolute against the current directory so that
+/// relative `$ref`s can be joined onto the result.
+pub fn base_url(base_uri: &str) -> Url {
+    match Url::parse(base_uri) {
+        // A single-letter scheme is a Windows drive, not a URL
+        Ok(url) if url.scheme().len() > 1 => url,
+        _ => std::path::absolute(base_uri)
+            .ok()
+            .and_then(|p| Url::from_file_path(p).ok())
+            .unwrap_or_else(|| Url::parse("file:///tmp").unwrap()),
+    }
+}
+
 /// Fetch schema text from a URL or file path, with caching.
-pub fn fetch_content(url: &str, cache: &mut ContentCache) -> Result<String, String> {
+///
+/// Catalog entries take precedence over the network; an `offline` cache
+/// fails for remote URIs the catalog does not cover.
+pub fn fetch_content(url: &str, cache: &mut ContentCache) -> Result<String, Error> {
     if let Some(cached) = cache.get(url) {
         return Ok(cached.clone());
     }
 
-    let parsed = Url::parse(url).map_err(|e| format!("Invalid URL: {e}"))?;
+    let fetch_error = |message: String| Error::Fetch {
+        uri: url.to_string(),
+        message,
+    };
+
+    if let Some(path) = cache.catalog.lookup(url) {
+        let content = fs::read_to_string(&path).map_err(|source| Error::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        cache.insert(url, content.clone());
+        return Ok(content);
+    }
+
+    let parsed = base_url(url);
 
     let content = match parsed.scheme() {
         "http" | "https" => {
+            if cache.offline {
+                return Err(fetch_error(
+                    "not in the catalog and fetching is offline".to_string(),
+                ));
+            }
             let client = Client::builder()
                 .timeout(Duration::from_secs(30))
                 .build()
-                .map_err(|e| format!("Client build error: {e}"))?;
+                .map_err(|e| fetch_error(format!("client build error: {e}")))?;
             let resp = client
                 .get(url)
                 .send()
-                .map_err(|e| format!("HTTP request error: {e}"))?;
+                .map_err(|e| fetch_error(format!("HTTP request error: {e}")))?;
             resp.text()
-                .map_err(|e| format!("Error reading response: {e}"))?
+                .map_err(|e| fetch_error(format!("error reading response: {e}")))?
         }
-        "file" | "" => {
+        "file" => {
             let path = parsed
                 .to_file_path()
-                .map_err(|_| format!("Invalid file URL: {url}"))?;
-            fs::read_to_string(&path).map_err(|e| format!("File read error from {path:?}: {e}"))?
+                .map_err(|_| fetch_error("invalid file URL".to_string()))?;
+            fs::read_to_string(&path).map_err(|source| Error::Io {
+                path: path.display().to_string(),
+                source,
+            })?
         }
-        _ => return Err(format!("Unsupported scheme: {}", parsed.scheme())),
+        scheme => return Err(fetch_error(format!("unsupported scheme {scheme}"))),
     };
 
     cache.insert(url, content.clone());
@@ -78,25 +188,37 @@ pub fn resolve_reference(
     base_uri: &str,
     json_doc: &Value,
     cache: &mut ContentCache,
-) -> Result<(Value, Value), String> {
+) -> Result<(Value, Value), Error> {
     let ref_str = json_type
         .get("$ref")
         .and_then(|v| v.as_str())
-        .ok_or("Missing $ref")?;
+        .unwrap_or_default();
+    let unresolvable = |message: String| Error::UnresolvableRef {
+        reference: ref_str.to_string(),
+        message,
+    };
+    if ref_str.is_empty() {
+        return Err(unresolvable("missing $ref".to_string()));
+    }
 
     let parsed = Url::options()
-        .base_url(Some(&Url::parse(base_uri).unwrap()))
+        .base_url(Some(&base_url(base_uri)))
         .parse(ref_str)
-        .map_err(|e| format!("Invalid $ref {ref_str}: {e}"))?;
+        .map_err(|e| unresolvable(format!("invalid URI: {e}")))?;
 
     let mut content = None;
     if ["http", "https", "file"].contains(&parsed.scheme()) {
-        let text = fetch_content(parsed.as_str(), cache)?;
-        content = Some(text);
+        let mut doc_url = parsed.clone();
+        doc_url.set_fragment(None);
+        let text = fetch_content(doc_url.as_str(), cache)?;
+        content = Some((doc_url, text));
     }
 
-    let schema_doc: Value = if let Some(txt) = content {
-        serde_json::from_str(&txt).map_err(|e| format!("JSON parse error: {e}"))?
+    let schema_doc: Value = if let Some((doc_url, txt)) = content {
+        serde_json::from_str(&txt).map_err(|source| Error::Json {
+            uri: doc_url.to_string(),
+            source,
+        })?
     } else {
         json_doc.clone()
     };
@@ -111,7 +233,7 @@ pub fn resolve_reference(
 
         let resolved = schema_doc
             .pointer(&pointer)
-            .ok_or_else(|| format!("Invalid JSON Pointer fragment: {pointer}"))?;
+            .ok_or_else(|| unresolvable(format!("no subschema at {pointer}")))?;
         Ok((resolved.clone(), schema_doc))
     } else {
         Ok((schema_doc.clone(), schema_doc))
@@ -0,0 +1,273 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use url::Url;
+
+/// Keywords whose values are data rather than subschemas, so any `$id`
+/// inside them must not be indexed.
+const NON_SCHEMA_KEYWORDS: &[&str] = &["const", "default", "enum", "examples"];
+
+/// A position inside an indexed document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Retrieval URI of the document.
+    pub document: String,
+    /// JSON Pointer to the subschema within the document.
+    pub pointer: String,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{}", self.document, self.pointer)
+    }
+}
+
+/// A schema resource: a document root or an embedded subschema with `$id`.
+#[derive(Debug, Clone)]
+struct Resource {
+    location: Location,
+    /// Whether the URI was declared with `$id` rather than taken from retrieval.
+    explicit_id: bool,
+}
+
+/// Index of the schema resources and anchors across all loaded documents.
+///
+/// JSON Schema 2020-12 lets any subschema declare its own `$id` (changing the
+/// base URI for everything beneath it) and `$anchor` (a plain-name fragment).
+/// The index maps each absolute URI to the document and JSON Pointer it names,
+/// so that `$ref`s can be resolved without re-walking documents.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceIndex {
+    documents: HashMap<String, Arc<Value>>,
+    resources: HashMap<String, Resource>,
+    anchors: HashMap<String, Location>,
+    /// `$dynamicAnchor`s, keyed like `anchors`.
+    dynamic_anchors: HashMap<String, Location>,
+    /// Resources whose root declares `"$recursiveAnchor": true` (draft 2019-09).
+    recursive_anchors: HashSet<String>,
+}
+
+impl ResourceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a document retrieved from `retrieval_uri`, indexing its resources.
+    pub fn add_document(&mut self, retrieval_uri: &Url, document: Value) {
+        let key = without_fragment(retrieval_uri);
+        self.resources.insert(
+            key.to_string(),
+            Resource {
+                location: Location {
+                    document: key.to_string(),
+                    pointer: String::new(),
+                },
+                explicit_id: false,
+            },
+        );
+        self.index_subschema(&document, &key, &key, "");
+        self.documents.insert(key.to_string(), Arc::new(document));
+    }
+
+    fn index_subschema(&mut self, schema: &Value, document: &Url, base: &Url, pointer: &str) {
+        match schema {
+            Value::Object(obj) => {
+                let mut base = base.clone();
+                if let Some(id) = obj.get("$id").and_then(|v| v.as_str()) {
+                    if let Some(anchor) = id.strip_prefix('#') {
+                        // Pre-2019-09 plain-name fragment identifier
+                        self.add_anchor(document, &base, anchor, pointer);
+                    } else if let Ok(joined) = base.join(id) {
+                        base = without_fragment(&joined);
+                        self.resources.insert(
+                            base.to_string(),
+                            Resource {
+                                location: Location {
+                                    document: document.to_string(),
+                                    pointer: pointer.to_string(),
+                                },
+                                explicit_id: true,
+                            },
+                        );
+                    }
+                }
+                if let Some(anchor) = obj.get("$anchor").and_then(|v| v.as_str()) {
+                    self.add_anchor(document, &base, anchor, pointer);
+                }
+                if let Some(anchor) = obj.get("$dynamicAnchor").and_then(|v| v.as_str()) {
+                    // A dynamic anchor is also a plain anchor for ordinary `$ref`s
+                    self.add_anchor(document, &base, anchor, pointer);
+                    self.dynamic_anchors.insert(
+                        format!("{}#{}", base, anchor),
+                        Location {
+                            document: document.to_string(),
+                            pointer: pointer.to_string(),
+                        },
+                    );
+                }
+                if obj.get("$recursiveAnchor").and_then(|v| v.as_bool()) == Some(true) {
+                    self.recursive_anchors.insert(base.to_string());
+                }
+                for (key, value) in obj {
+                    if NON_SCHEMA_KEYWORDS.contains(&key.as_str()) {
+                        continue;
+                    }
+                    let child = format!("{}/{}", pointer, escape_pointer_token(key));
+                    self.index_subschema(value, document, &base, &child);
+                }
+            }
+            Value::Array(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    let child = format!("{}/{}", pointer, idx);
+                    self.index_subschema(item, document, base, &child);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_anchor(&mut self, document: &Url, base: &Url, anchor: &str, pointer: &str) {
+        self.anchors.insert(
+            format!("{}#{}", base, anchor),
+            Location {
+                document: document.to_string(),
+                pointer: pointer.to_string(),
+            },
+        );
+    }
+
+    /// Whether the resource named by `uri` (ignoring any fragment) is indexed.
+    pub fn has_resource(&self, uri: &Url) -> bool {
+        self.resources.contains_key(without_fragment(uri).as_str())
+    }
+
+    /// Whether `uri` names the root of a document rather than an embedded resource.
+    pub fn is_document_root(&self, uri: &Url) -> bool {
+        self.resources
+            .get(without_fragment(uri).as_str())
+            .is_some_and(|r| r.location.pointer.is_empty())
+    }
+
+    /// Locate the subschema named by an absolute URI.
+    ///
+    /// The fragment may be empty, a JSON Pointer, or a plain-name anchor.
+    pub fn locate(&self, uri: &Url) -> Option<Location> {
+        let resource = without_fragment(uri);
+        match uri.fragment().filter(|f| !f.is_empty()) {
+            None => self
+                .resources
+                .get(resource.as_str())
+                .map(|r| r.location.clone()),
+            Some(fragment) if fragment.starts_with('/') => {
+                let mut location = self.resources.get(resource.as_str())?.location.clone();
+                location.pointer.push_str(&percent_decode(fragment));
+                Some(location)
+            }
+            Some(anchor) => self
+                .anchors
+                .get(&format!("{}#{}", resource, percent_decode(anchor)))
+                .cloned(),
+        }
+    }
+
+    /// The subschema declaring `$dynamicAnchor: name` in the resource `resource`.
+    pub fn dynamic_anchor(&self, resource: &str, name: &str) -> Option<Location> {
+        self.dynamic_anchors
+            .get(&format!("{}#{}", resource, name))
+            .cloned()
+    }
+
+    /// Whether the root of the resource `resource` declares `$recursiveAnchor: true`.
+    pub fn has_recursive_anchor(&self, resource: &str) -> bool {
+        self.recursive_anchors.contains(resource)
+    }
+
+    /// The base URI in effect at `location`, and whether it came from an `$id`.
+    ///
+    /// This is the URI of the innermost resource enclosing the location.
+    pub fn base_of(&self, location: &Location) -> Option<(Url, bool)> {
+        self.resources
+            .iter()
+            .filter(|(_, r)| {
+                r.location.document == location.document
+                    && pointer_contains(&r.location.pointer, &location.pointer)
+            })
+            .max_by_key(|(uri, r)| (r.location.pointer.len(), r.explicit_id, uri.len()))
+            .and_then(|(uri, r)| Url::parse(uri).ok().map(|u| (u, r.explicit_id)))
+    }
+
+    /// The base URI in effect just above `location`, i.e. the one its own
+    /// `$id` (if any) is resolved against.
+    pub fn base_above(&self, location: &Location) -> Option<Url> {
+        match location.pointer.rfind('/') {
+            Some(idx) => self.base_of(&Location {
+                document: location.document.clone(),
+                pointer: location.pointer[..idx].to_string(),
+            }),
+            // Document roots resolve against their retrieval URI
+            None => Url::parse(&location.document).ok().map(|u| (u, false)),
+        }
+        .map(|(uri, _)| uri)
+    }
+
+    /// The document stored under a retrieval URI.
+    pub fn document(&self, document: &str) -> Option<Arc<Value>> {
+        self.documents.get(document).cloned()
+    }
+
+    /// The subschema at `location`.
+    pub fn subschema(&self, location: &Location) -> Option<Value> {
+        self.documents
+            .get(&location.document)?
+            .pointer(&location.pointer)
+            .cloned()
+    }
+}
+
+/// Resolve the `$id` of `schema` (if any) against `base_uri`.
+///
+/// Plain-name `$id`s (`"#foo"`) are anchors and leave the base unchanged.
+pub fn rebase(base_uri: &str, schema: &Value) -> String {
+    match schema.get("$id").and_then(|v| v.as_str()) {
+        Some(id) if !id.starts_with('#') => super::references::base_url(base_uri)
+            .join(id)
+            .map(|u| without_fragment(&u).to_string())
+            .unwrap_or_else(|_| base_uri.to_string()),
+        _ => base_uri.to_string(),
+    }
+}
+
+fn without_fragment(uri: &Url) -> Url {
+    let mut uri = uri.clone();
+    uri.set_fragment(None);
+    uri
+}
+
+/// Whether the pointer `outer` is `inner` or one of its ancestors.
+fn pointer_contains(outer: &str, inner: &str) -> bool {
+    inner == outer || (inner.starts_with(outer) && inner.as_bytes().get(outer.len()) == Some(&b'/'))
+}
+
+fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Decode `%XX` escapes in a URI fragment.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                out.push(hi << 4 | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
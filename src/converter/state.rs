@@ -1,15 +1,54 @@
-use crate::avro::AvroType;
+use crate::converter::builder::JsonToAvroConverterBuilder;
+use crate::converter::diagnostics::Diagnostics;
+use crate::converter::options::ConverterOptions;
+use crate::converter::references::ContentCache;
+use crate::converter::resources::ResourceIndex;
+use crate::error::Error;
 use std::collections::HashMap;
 
 /// Holds the state for converting JSON Schema → Avro Schema.
+///
+/// Build one with [`JsonToAvroConverter::builder`] and reuse it across
+/// schemas: the content cache and the index of loaded documents persist, so
+/// external documents are fetched once per converter, while the registry of
+/// named types is reset for each conversion so that every schema is
+/// self-contained.
+#[derive(Debug)]
 pub struct JsonToAvroConverter {
-    pub imported_types: HashMap<String, AvroType>,
+    /// Full Avro names of types converted from `$ref` targets, keyed by location.
+    pub imported_types: HashMap<String, String>,
     pub root_namespace: String,
+    /// Base URI of the root document, used to tell local refs from external ones.
+    pub root_base_uri: String,
+    /// Nesting depth beyond which subschemas become a generic type.
     pub max_recursion_depth: usize,
-    pub content_cache: HashMap<String, String>,
-    pub utility_namespace: String,
+    pub content_cache: ContentCache,
+    /// Schema resources (`$id`, `$anchor`) of every document loaded so far.
+    pub resources: ResourceIndex,
+    /// Locations of local `$ref`s currently being converted in place.
+    pub inline_refs: Vec<String>,
+    /// Location of the subschema about to be converted into a record, claimed
+    /// by the record so that `$ref`s back to it can be recognised.
+    pub pending_location: Option<String>,
+    /// Locations and full names of the records being converted, innermost last.
+    pub open_records: Vec<(String, String)>,
+    /// Resource URIs entered on the way to the current subschema, outermost
+    /// first; `$dynamicRef` and `$recursiveRef` resolve against it.
+    pub dynamic_scope: Vec<String>,
+    /// Namespace for converted types, instead of one derived from the source.
+    pub namespace: Option<String>,
+    /// Namespace for utility types, instead of `<namespace>.utility`.
+    pub utility_namespace: Option<String>,
+    /// Return the top-level records as a list instead of one nested schema.
     pub split_top_level_records: bool,
-    pub root_class_name: String,
+    /// Name of the root type, instead of the schema's title or file stem.
+    pub root_class_name: Option<String>,
+    /// How JSON Schema formats and keywords map onto Avro types.
+    pub options: ConverterOptions,
+    /// First failure hit during the current conversion, if any.
+    pub error: Option<Error>,
+    /// Warnings and notes collected during the current conversion.
+    pub diagnostics: Diagnostics,
 }
 
 impl Default for JsonToAvroConverter {
@@ -19,16 +58,36 @@ impl Default for JsonToAvroConverter {
 }
 
 impl JsonToAvroConverter {
+    /// Start configuring a converter.
+    pub fn builder() -> JsonToAvroConverterBuilder {
+        JsonToAvroConverterBuilder::new()
+    }
+
     /// Create a new converter with default settings.
     pub fn new() -> Self {
         Self {
             imported_types: HashMap::new(),
             root_namespace: "example.com".to_string(),
+            root_base_uri: String::new(),
             max_recursion_depth: 40,
-            content_cache: HashMap::new(),
-            utility_namespace: "utility.vasters.com".to_string(),
+            content_cache: ContentCache::new(),
+            resources: ResourceIndex::new(),
+            inline_refs: Vec::new(),
+            dynamic_scope: Vec::new(),
+            pending_location: None,
+            open_records: Vec::new(),
+            namespace: None,
+            utility_namespace: None,
             split_top_level_records: false,
-            root_class_name: "document".to_string(),
+            root_class_name: None,
+            options: ConverterOptions::default(),
+            error: None,
+            diagnostics: Diagnostics::new(),
         }
     }
+
+    /// Record a conversion failure; the entry points return the first one.
+    pub fn fail(&mut self, error: Error) {
+        self.error.get_or_insert(error);
+    }
 }
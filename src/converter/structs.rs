@@ -1,7 +1,9 @@
 use serde_json::json;
 use serde_json::Value;
 
-use crate::common::names::avro_name;
+use crate::common::names::{avro_name, avro_name_with_altname};
+use crate::converter::mapping::annotate;
+use crate::converter::options::EnumValues;
 
 /// Create an Avro record type.
 ///
@@ -15,6 +17,15 @@ pub fn create_avro_record(name: &str, namespace: &str, fields: Vec<Value>) -> Va
     })
 }
 
+/// Purpose under which original JSON names are kept in `altnames`.
+pub const ALTNAME_PURPOSE: &str = "json";
+
+/// Keep the JSON name a record, enum or field had before `avro_name`
+/// normalized it, as read back by [`altname`](crate::common::names::altname).
+pub fn set_altname(target: &mut Value, original: &str) {
+    target["altnames"] = json!({ ALTNAME_PURPOSE: original });
+}
+
 /// Create a wrapper record around another type.
 ///
 /// Useful when Avro requires a record but the JSON Schema root
@@ -31,6 +42,7 @@ pub fn create_wrapper_record(
         wrapper_namespace,
         vec![json!({ "name": wrapper_field, "type": avro_type })],
     );
+    annotate(&mut record, "wrapper", json!(wrapper_field));
 
     if !dependencies.is_empty() {
         record["dependencies"] = Value::Array(
@@ -46,15 +58,116 @@ pub fn create_wrapper_record(
 
 /// Create an Avro enum type.
 ///
-/// Symbols are automatically normalized with `avro_name`.
+/// The name and symbols are automatically normalized with `avro_name`; the
+/// originals of those it changes are kept as `altnames` and `altsymbols`.
 pub fn create_enum_type(name: &str, namespace: &str, symbols: &[String]) -> Value {
-    let symbols: Vec<String> = symbols.iter().map(|s| avro_name(s)).collect();
-    json!({
+    let values: Vec<Value> = symbols.iter().cloned().map(Value::String).collect();
+    create_symbol_enum(name, namespace, &values)
+}
+
+/// Create the Avro type of the values of an `enum` or `const`.
+///
+/// Strings become an enum as with [`create_enum_type`]; other values are
+/// represented as `strategy` says. Arrays and objects, which have no Avro
+/// type of their own to list them in, always get symbols. `None` if there are
+/// no values.
+pub fn create_enum_of_values(
+    name: &str,
+    namespace: &str,
+    values: &[Value],
+    strategy: EnumValues,
+) -> Option<Value> {
+    if values.is_empty() {
+        return None;
+    }
+    let strings: Vec<String> = values
+        .iter()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect();
+    if strings.len() == values.len() {
+        return Some(create_enum_type(name, namespace, &strings));
+    }
+    let compound = values.iter().any(|v| v.is_array() || v.is_object());
+    if strategy == EnumValues::Symbols || compound {
+        return Some(create_symbol_enum(name, namespace, values));
+    }
+
+    let of_kind = |kind: fn(&Value) -> bool| -> Vec<Value> {
+        values.iter().filter(|v| kind(v)).cloned().collect()
+    };
+    let mut branches = Vec::new();
+    if values.iter().any(Value::is_null) {
+        branches.push(json!("null"));
+    }
+    if !strings.is_empty() {
+        branches.push(create_enum_type(name, namespace, &strings));
+    }
+    let booleans = of_kind(Value::is_boolean);
+    if !booleans.is_empty() {
+        branches.push(json!({"type": "boolean", "enum": booleans}));
+    }
+    let numbers = of_kind(Value::is_number);
+    if !numbers.is_empty() {
+        let fits = |range: std::ops::RangeInclusive<i64>| {
+            numbers
+                .iter()
+                .all(|n| n.as_i64().is_some_and(|n| range.contains(&n)))
+        };
+        let number_type = if fits(i32::MIN as i64..=i32::MAX as i64) {
+            "int"
+        } else if fits(i64::MIN..=i64::MAX) {
+            "long"
+        } else {
+            "double"
+        };
+        branches.push(json!({"type": number_type, "enum": numbers}));
+    }
+    Some(if branches.len() == 1 {
+        branches.remove(0)
+    } else {
+        Value::Array(branches)
+    })
+}
+
+/// An Avro enum with a symbol per value, each normalized with `avro_name`
+/// (and numbered if it is already taken). Values that aren't their symbol
+/// are kept in `altsymbols`.
+fn create_symbol_enum(name: &str, namespace: &str, values: &[Value]) -> Value {
+    let (enum_name, original_name) = avro_name_with_altname(name);
+    let mut symbols: Vec<String> = Vec::new();
+    let mut originals = serde_json::Map::new();
+    for (i, value) in values.iter().enumerate() {
+        if values[..i].contains(value) {
+            continue;
+        }
+        let base = match value {
+            Value::String(s) => avro_name(s),
+            other => avro_name(&other.to_string()),
+        };
+        let mut symbol = base.clone();
+        let mut n = 2;
+        while symbols.contains(&symbol) {
+            symbol = format!("{base}_{n}");
+            n += 1;
+        }
+        if value.as_str() != Some(symbol.as_str()) {
+            originals.insert(symbol.clone(), value.clone());
+        }
+        symbols.push(symbol);
+    }
+    let mut avro_enum = json!({
         "type": "enum",
-        "name": avro_name(name),
+        "name": enum_name,
         "namespace": namespace,
         "symbols": symbols
-    })
+    });
+    if let Some(original) = original_name {
+        set_altname(&mut avro_enum, &original);
+    }
+    if !originals.is_empty() {
+        avro_enum["altsymbols"] = json!({ ALTNAME_PURPOSE: originals });
+    }
+    avro_enum
 }
 
 /// Create an Avro array type.
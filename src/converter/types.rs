@@ -1,4 +1,8 @@
 use crate::common::generic_type;
+use crate::common::names::avro_name;
+use crate::converter::diagnostics::Diagnostics;
+use crate::converter::options::ConverterOptions;
+use crate::converter::structs::create_enum_of_values;
 use serde_json::{json, Value};
 
 /// Ensure the given type has a `"type"` field if required.
@@ -27,7 +31,9 @@ pub fn ensure_type(value: &Value) -> Value {
 ///
 /// Handles:
 /// - `"string"`, `"integer"`, `"number"`, `"boolean"`
-/// - JSON Schema `format` annotations (`date-time`, `time`, `duration`, `uuid`)
+/// - JSON Schema `format` annotations (`date-time`, `date`, `time`, `duration`, `uuid`),
+///   with timestamp precision taken from `options`; other formats keep the base
+///   primitive and are recorded as a `"format"` attribute
 /// - Enum → Avro enum
 pub fn json_schema_primitive_to_avro_type(
     json_primitive: &Value,
@@ -37,6 +43,7 @@ pub fn json_schema_primitive_to_avro_type(
     field_name: &str,
     namespace: &str,
     dependencies: &mut Vec<String>,
+    options: &ConverterOptions,
 ) -> Value {
     if json_primitive.is_array() {
         // Union type
@@ -52,6 +59,7 @@ pub fn json_schema_primitive_to_avro_type(
                 field_name,
                 namespace,
                 dependencies,
+                options,
             );
             union.push(subtype);
         }
@@ -63,13 +71,19 @@ pub fn json_schema_primitive_to_avro_type(
     let mut avro_type = match primitive_str {
         "string" => Value::String("string".to_string()),
         "integer" => {
-            if format == Some("int64") {
+            if matches!(format, Some("int64" | "uint32")) {
                 Value::String("long".to_string())
             } else {
                 Value::String("int".to_string())
             }
         }
-        "number" => Value::String("float".to_string()),
+        "number" => {
+            if format == Some("double") {
+                Value::String("double".to_string())
+            } else {
+                Value::String("float".to_string())
+            }
+        }
         "boolean" => Value::String("boolean".to_string()),
         other => {
             if !other.is_empty() {
@@ -81,39 +95,357 @@ pub fn json_schema_primitive_to_avro_type(
 
     if let Some(fmt) = format {
         match fmt {
-            "date-time" | "date" => {
+            "date-time" => {
+                avro_type = options.timestamp_type();
+            }
+            "date" => {
                 avro_type = json!({"type": "int", "logicalType": "date"});
             }
             "time" => {
-                avro_type = json!({"type": "int", "logicalType": "time-millis"});
+                avro_type = options.time_type();
             }
             "duration" => {
-                avro_type = json!({"type": "fixed", "size": 12, "logicalType": "duration"});
+                avro_type = json!({
+                    "type": "fixed",
+                    "name": avro_name(&format!("{}_{}", record_name, field_name)),
+                    "namespace": namespace,
+                    "size": 12,
+                    "logicalType": "duration"
+                });
             }
             "uuid" => {
                 avro_type = json!({"type": "string", "logicalType": "uuid"});
             }
-            _other => {
-                return Value::Array(vec![]); // bail for unsupported format
+            "int32" | "int64" | "uint32" | "uint64" | "float" | "double" => {}
+            other => {
+                // Annotation-only or unknown format: keep the base primitive and
+                // carry the format along as a custom attribute
+                if let Some(primitive) = avro_type.as_str() {
+                    avro_type = json!({"type": primitive, "format": other});
+                }
             }
         }
     }
 
     // Enum values override primitive if present
-    if let Some(enum_vals) = enum_values {
-        let symbols: Vec<String> = enum_vals
+    if let Some(enum_type) = enum_values.and_then(|values| {
+        create_enum_of_values(
+            &format!("{record_name}_{field_name}"),
+            namespace,
+            values,
+            options.enum_values,
+        )
+    }) {
+        avro_type = enum_type;
+    }
+
+    avro_type
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Convert a primitive whose Avro type depends on keywords beyond `type`/`format`:
+/// fixed-point `number`s become `decimal`s and binary `string`s `bytes`/`fixed`.
+///
+/// Returns `None` when the plain primitive mapping applies.
+pub fn json_schema_constrained_primitive_to_avro_type(
+    json_primitive: &str,
+    json_object: &Value,
+    record_name: &str,
+    field_name: &str,
+    namespace: &str,
+    options: &ConverterOptions,
+    diagnostics: &mut Diagnostics,
+) -> Option<Value> {
+    match json_primitive {
+        "number" => {
+            json_schema_decimal_type(json_object, record_name, field_name, namespace, options)
+        }
+        "string" => {
+            json_schema_binary_type(json_object, record_name, field_name, namespace, diagnostics)
+        }
+        _ => None,
+    }
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Convert a JSON Schema `string` carrying binary content into Avro `bytes`.
+///
+/// Binary content is marked by `contentEncoding: base64` or `format: byte`
+/// (base64 text) or `format: binary` (raw octets). When `minLength` equals
+/// `maxLength` the content has a fixed size and becomes a named `fixed`; for
+/// base64 that is the decoded size, which only unpadded lengths determine
+/// (a padded length of 44 may hold 31, 32 or 33 bytes).
+pub fn json_schema_binary_type(
+    json_object: &Value,
+    record_name: &str,
+    field_name: &str,
+    namespace: &str,
+    diagnostics: &mut Diagnostics,
+) -> Option<Value> {
+    let format = json_object.get("format").and_then(|f| f.as_str());
+    let base64 = json_object
+        .get("contentEncoding")
+        .and_then(|e| e.as_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("base64"))
+        || format == Some("byte");
+    if !base64 && format != Some("binary") {
+        return None;
+    }
+
+    let length = |key: &str| json_object.get(key).and_then(|v| v.as_u64());
+    let size = match (length("minLength"), length("maxLength")) {
+        (Some(len), Some(max)) if len == max && base64 => match len % 4 {
+            // Unpadded: every length maps to exactly one decoded size
+            2 | 3 => Some(len * 3 / 4),
+            _ => {
+                diagnostics.warn(
+                    "base64-size",
+                    Some("maxLength"),
+                    format!("base64 length {len} of {field_name} does not determine a decoded size; using bytes"),
+                );
+                None
+            }
+        },
+        (Some(len), Some(max)) if len == max => Some(len),
+        _ => None,
+    };
+    Some(match size.filter(|s| *s > 0) {
+        Some(size) => json!({
+            "type": "fixed",
+            "name": avro_name(&format!("{}_{}", record_name, field_name)),
+            "namespace": namespace,
+            "size": size
+        }),
+        None => json!("bytes"),
+    })
+}
+
+/// Integer value of a JSON number bound, rounded inwards for exclusive bounds.
+fn integer_bound(number: &serde_json::Number, exclusive: bool, upper: bool) -> i128 {
+    let value = number
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| number.as_u64().map(i128::from));
+    match (value, upper) {
+        (Some(v), true) => v - exclusive as i128,
+        (Some(v), false) => v + exclusive as i128,
+        // Fractional or huge bounds: the nearest integer inside the range
+        (None, true) => {
+            let f = number.as_f64().unwrap_or(f64::MAX);
+            (if exclusive { f.ceil() - 1.0 } else { f.floor() }) as i128
+        }
+        (None, false) => {
+            let f = number.as_f64().unwrap_or(f64::MIN);
+            (if exclusive { f.floor() + 1.0 } else { f.ceil() }) as i128
+        }
+    }
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Choose the Avro width of a JSON Schema `integer` from its bounds and format.
+///
+/// `avro_type` is the type already derived from the format (`int` unless
+/// `int64`/`uint32`). It is widened to `long` when `minimum`, `maximum`,
+/// `exclusiveMinimum` or `exclusiveMaximum` fall outside the 32-bit range,
+/// with a warning if that contradicts `format: int32`. Values beyond the
+/// 64-bit range (including `format: uint64`) cannot be a `long`; they warn
+/// and become a `decimal` with scale 0 so that no value is lost.
+pub fn fit_integer_to_bounds(
+    avro_type: Value,
+    json_object: &Value,
+    field_name: &str,
+    diagnostics: &mut Diagnostics,
+) -> Value {
+    let base = match &avro_type {
+        Value::String(t) => t.as_str(),
+        Value::Object(obj) => obj.get("type").and_then(|t| t.as_str()).unwrap_or(""),
+        _ => "",
+    }
+    .to_string();
+    if base != "int" && base != "long" {
+        return avro_type;
+    }
+    let format = json_object.get("format").and_then(|f| f.as_str());
+    let bound = |key: &str, exclusive: bool, upper: bool| {
+        json_object
+            .get(key)
+            .and_then(|v| v.as_number())
+            .map(|n| integer_bound(n, exclusive, upper))
+    };
+    let mut lows: Vec<i128> = [
+        bound("minimum", false, false),
+        bound("exclusiveMinimum", true, false),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let mut highs: Vec<i128> = [
+        bound("maximum", false, true),
+        bound("exclusiveMaximum", true, true),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if format == Some("uint64") {
+        lows.push(0);
+        highs.push(u64::MAX as i128);
+    }
+    let low = lows.into_iter().max();
+    let high = highs.into_iter().min();
+    let fits = |min: i128, max: i128| low.is_none_or(|l| l >= min) && high.is_none_or(|h| h <= max);
+
+    let width = if fits(i32::MIN as i128, i32::MAX as i128) {
+        base
+    } else if fits(i64::MIN as i128, i64::MAX as i128) {
+        if format == Some("int32") {
+            diagnostics.warn(
+                "int32-exceeded",
+                Some("format"),
+                format!("bounds of {field_name} exceed format int32; using long"),
+            );
+        }
+        "long".to_string()
+    } else {
+        let largest = low
+            .into_iter()
+            .chain(high)
+            .map(|v| v.unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        diagnostics.warn(
+            "long-exceeded",
+            None,
+            format!("bounds of {field_name} exceed the range of an Avro long; using decimal"),
+        );
+        return json!({
+            "type": "bytes",
+            "logicalType": "decimal",
+            "precision": largest.to_string().len(),
+            "scale": 0
+        });
+    };
+
+    match avro_type {
+        Value::Object(mut obj) => {
+            obj.insert("type".to_string(), json!(width));
+            Value::Object(obj)
+        }
+        _ => json!(width),
+    }
+}
+
+/// Number of decimal places needed to represent a JSON number exactly.
+fn decimal_places(number: &serde_json::Number) -> u32 {
+    let text = number.to_string().to_ascii_lowercase();
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((m, e)) => (m.to_string(), e.parse::<i64>().unwrap_or(0)),
+        None => (text.clone(), 0),
+    };
+    let fraction = mantissa
+        .split_once('.')
+        .map(|(_, f)| f.trim_end_matches('0').len() as i64)
+        .unwrap_or(0);
+    (fraction - exponent).max(0) as u32
+}
+
+/// Number of digits before the decimal point of `value`.
+fn integer_digits(value: f64) -> u32 {
+    let whole = value.abs().trunc();
+    if whole < 1.0 {
+        1
+    } else {
+        whole.log10().floor() as u32 + 1
+    }
+}
+
+/// Smallest `fixed` size able to hold a two's complement decimal of `precision` digits.
+fn decimal_fixed_size(precision: u32) -> u32 {
+    ((precision as f64 * 10f64.log2() + 1.0) / 8.0).ceil() as u32
+}
+
+#[cfg_attr(feature = "trace", crustrace::instrument)]
+/// Convert a JSON Schema `number` into an Avro `decimal`, if its constraints call for one.
+///
+/// A decimal is produced when the schema has:
+/// - an `x-avro-decimal` annotation, either `true` or `{"precision", "scale", "fixed"}`;
+/// - a fractional `multipleOf` (`0.01` gives scale 2);
+/// - fractional `minimum`/`maximum` bounds (`999.99` gives scale 2).
+///
+/// The bounds, when present, determine the precision; otherwise
+/// `options.decimal_precision` applies. Returns `None` for plain numbers.
+pub fn json_schema_decimal_type(
+    json_object: &Value,
+    record_name: &str,
+    field_name: &str,
+    namespace: &str,
+    options: &ConverterOptions,
+) -> Option<Value> {
+    let annotation = json_object.get("x-avro-decimal");
+    if annotation == Some(&Value::Bool(false)) {
+        return None;
+    }
+    let bounds: Vec<&serde_json::Number> =
+        ["minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum"]
             .iter()
-            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .filter_map(|k| json_object.get(*k).and_then(|v| v.as_number()))
             .collect();
-        if !symbols.is_empty() {
-            avro_type = json!({
-                "type": "enum",
-                "name": format!("{}_{}", record_name, field_name),
-                "namespace": namespace,
-                "symbols": symbols
-            });
-        }
+    let multiple_of_scale = json_object
+        .get("multipleOf")
+        .and_then(|v| v.as_number())
+        .map(decimal_places)
+        .filter(|scale| *scale > 0);
+    let bounds_scale = bounds
+        .iter()
+        .map(|n| decimal_places(n))
+        .max()
+        .filter(|s| *s > 0);
+
+    let derived_scale = multiple_of_scale.or(bounds_scale);
+    if annotation.is_none() && derived_scale.is_none() {
+        return None;
     }
+    let explicit = |key: &str| {
+        annotation
+            .and_then(|a| a.get(key))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+    };
 
-    avro_type
+    let scale = explicit("scale")
+        .or(derived_scale)
+        .unwrap_or(options.decimal_scale);
+    let precision = explicit("precision")
+        .or_else(|| {
+            bounds
+                .iter()
+                .filter_map(|n| n.as_f64())
+                .map(integer_digits)
+                .max()
+                .map(|digits| digits + scale)
+        })
+        .unwrap_or(options.decimal_precision)
+        .max(scale.max(1));
+    let fixed = annotation
+        .and_then(|a| a.get("fixed"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(options.decimal_fixed);
+
+    Some(if fixed {
+        json!({
+            "type": "fixed",
+            "name": avro_name(&format!("{}_{}", record_name, field_name)),
+            "namespace": namespace,
+            "size": decimal_fixed_size(precision),
+            "logicalType": "decimal",
+            "precision": precision,
+            "scale": scale
+        })
+    } else {
+        json!({
+            "type": "bytes",
+            "logicalType": "decimal",
+            "precision": precision,
+            "scale": scale
+        })
+    })
 }
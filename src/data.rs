@@ -0,0 +1,17 @@
+//! Avro data for JSON instances of the source JSON Schema.
+//!
+//! Values are laid out in the shape of the schema the converter generated:
+//! an instance of a root array is written through its `*_wrapper` record, a
+//! `oneOf` through its `*_union` record, and property names and enum values
+//! are matched to the [`avro_name`](crate::common::avro_name)s they were
+//! given. [`Decoder`] undoes this, and [`ocf`] stores the values in object
+//! container files.
+pub mod codec;
+pub mod decode;
+pub mod encode;
+pub mod ocf;
+
+pub use codec::Codec;
+pub use decode::Decoder;
+pub use encode::Encoder;
+pub use ocf::{Container, Writer};
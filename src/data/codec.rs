@@ -0,0 +1,86 @@
+//! Block compression codecs of Avro object container files.
+pub mod deflate;
+pub mod snappy;
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::error::Error;
+
+/// How the blocks of an object container file are compressed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    /// Uncompressed.
+    #[default]
+    Null,
+    /// Raw DEFLATE (RFC 1951), without a zlib header.
+    Deflate,
+    /// Snappy, followed by the big-endian CRC-32 of the uncompressed block.
+    Snappy,
+}
+
+impl Codec {
+    /// The codec named by an `avro.codec` header.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "null" => Some(Codec::Null),
+            "deflate" => Some(Codec::Deflate),
+            "snappy" => Some(Codec::Snappy),
+            _ => None,
+        }
+    }
+
+    pub fn compress(self, block: &[u8]) -> Vec<u8> {
+        match self {
+            Codec::Null => block.to_vec(),
+            Codec::Deflate => deflate::compress(block),
+            Codec::Snappy => {
+                let mut out = snappy::compress(block);
+                out.extend(crc32(block).to_be_bytes());
+                out
+            }
+        }
+    }
+
+    pub fn decompress(self, block: &[u8]) -> Result<Vec<u8>, Error> {
+        let corrupt = |message: String| Error::InvalidContainer {
+            message: format!("corrupt {self} block: {message}"),
+        };
+        match self {
+            Codec::Null => Ok(block.to_vec()),
+            Codec::Deflate => deflate::decompress(block).map_err(corrupt),
+            Codec::Snappy => {
+                let Some(split) = block.len().checked_sub(4) else {
+                    return Err(corrupt("missing checksum".to_string()));
+                };
+                let (compressed, checksum) = block.split_at(split);
+                let out = snappy::decompress(compressed).map_err(corrupt)?;
+                if crc32(&out).to_be_bytes() != checksum {
+                    return Err(corrupt("checksum mismatch".to_string()));
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Codec::Null => "null",
+            Codec::Deflate => "deflate",
+            Codec::Snappy => "snappy",
+        })
+    }
+}
+
+/// CRC-32 (IEEE 802.3) of `bytes`.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
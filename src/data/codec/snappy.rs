@@ -0,0 +1,147 @@
+//! Snappy raw block format.
+//!
+//! Compression emits a literal for bytes without an earlier 4-byte match and
+//! a copy for each match, found through a hash table of recent positions.
+
+const MIN_MATCH: usize = 4;
+/// Largest offset a two-byte copy can express.
+const MAX_OFFSET: usize = u16::MAX as usize;
+
+fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn literal(out: &mut Vec<u8>, bytes: &[u8]) {
+    let n = bytes.len() - 1;
+    match n {
+        0..=59 => out.push((n as u8) << 2),
+        _ => {
+            let width = (usize::BITS - n.leading_zeros()).div_ceil(8) as usize;
+            out.push(((59 + width) as u8) << 2);
+            out.extend(&n.to_le_bytes()[..width]);
+        }
+    }
+    out.extend_from_slice(bytes);
+}
+
+fn copy(out: &mut Vec<u8>, offset: usize, mut length: usize) {
+    while length > 0 {
+        let n = length.min(64);
+        out.push((((n - 1) as u8) << 2) | 0b10);
+        out.extend((offset as u16).to_le_bytes());
+        length -= n;
+    }
+}
+
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() + input.len() / 6 + 8);
+    write_varint(&mut out, input.len());
+    let hash = |i: usize| {
+        let v = u32::from_le_bytes(input[i..i + 4].try_into().unwrap());
+        (v.wrapping_mul(0x1e35_a7bd) >> 18) as usize
+    };
+    let mut table = vec![usize::MAX; 1 << 14];
+    let (mut i, mut pending) = (0, 0);
+    while i + MIN_MATCH <= input.len() {
+        let h = hash(i);
+        let candidate = table[h];
+        table[h] = i;
+        if candidate == usize::MAX
+            || i - candidate > MAX_OFFSET
+            || input[candidate..candidate + MIN_MATCH] != input[i..i + MIN_MATCH]
+        {
+            i += 1;
+            continue;
+        }
+        let length = MIN_MATCH
+            + input[i + MIN_MATCH..]
+                .iter()
+                .zip(&input[candidate + MIN_MATCH..])
+                .take_while(|(a, b)| a == b)
+                .count();
+        if pending < i {
+            literal(&mut out, &input[pending..i]);
+        }
+        copy(&mut out, i - candidate, length);
+        i += length;
+        pending = i;
+    }
+    if pending < input.len() {
+        literal(&mut out, &input[pending..]);
+    }
+    out
+}
+
+pub fn decompress(input: &[u8]) -> Result<Vec<u8>, String> {
+    let truncated = || "unexpected end of data".to_string();
+    let mut pos = 0;
+    let mut length = 0usize;
+    for shift in (0..35).step_by(7) {
+        let byte = *input.get(pos).ok_or_else(truncated)?;
+        pos += 1;
+        length |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    // The length comes from the block, so only trust it as far as the input
+    // could plausibly expand
+    let mut out = Vec::with_capacity(length.min(input.len().saturating_mul(8)));
+    let overflow = || format!("decompressed more than the expected {length} bytes");
+    let take = |pos: &mut usize, n: usize| -> Result<usize, String> {
+        let bytes = input.get(*pos..*pos + n).ok_or_else(truncated)?;
+        *pos += n;
+        let mut le = [0u8; 8];
+        le[..n].copy_from_slice(bytes);
+        Ok(usize::from_le_bytes(le))
+    };
+    while pos < input.len() {
+        let tag = input[pos];
+        pos += 1;
+        let (offset, n) = match tag & 0b11 {
+            0 => {
+                let n = match tag >> 2 {
+                    n @ 0..=59 => n as usize,
+                    width => take(&mut pos, (width - 59) as usize)?,
+                } + 1;
+                let bytes = input.get(pos..pos + n).ok_or_else(truncated)?;
+                if out.len() + n > length {
+                    return Err(overflow());
+                }
+                out.extend_from_slice(bytes);
+                pos += n;
+                continue;
+            }
+            1 => {
+                let low = take(&mut pos, 1)?;
+                (
+                    (((tag >> 5) as usize) << 8) | low,
+                    ((tag >> 2) & 0b111) as usize + 4,
+                )
+            }
+            2 => (take(&mut pos, 2)?, (tag >> 2) as usize + 1),
+            _ => (take(&mut pos, 4)?, (tag >> 2) as usize + 1),
+        };
+        if offset == 0 || offset > out.len() {
+            return Err("copy offset out of range".to_string());
+        }
+        if out.len() + n > length {
+            return Err(overflow());
+        }
+        let start = out.len() - offset;
+        for k in 0..n {
+            out.push(out[start + k]);
+        }
+    }
+    if out.len() != length {
+        return Err(format!(
+            "decompressed {} bytes, expected {length}",
+            out.len()
+        ));
+    }
+    Ok(out)
+}
@@ -0,0 +1,501 @@
+//! JSON instances from the Avro binary encoding.
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+use super::encode::{collect, qualify, record_namespace, wrapped_field};
+use crate::avro::{AvroField, AvroType};
+use crate::converter::mapping::Mapping;
+use crate::converter::structs::ALTNAME_PURPOSE;
+use crate::error::Error;
+
+/// Reads JSON instances from the Avro binary encoding of a schema, undoing
+/// what [`Encoder`](super::Encoder) does to fit them to it.
+///
+/// The wrapper records the converter introduced are unwrapped, the map field
+/// holding a record's `patternProperties` is merged into its object, and
+/// optional properties that were absent (written as `null`) are left out.
+/// Binary values come back as base64, logical types in the JSON Schema format
+/// they were converted from, with timestamps in UTC. Decimals are numbers, or
+/// their exact text where a JSON number would round them.
+///
+/// Property names and enum values the converter renamed are restored from the
+/// schema's `altnames` and `altsymbols`, or from the conversion's [`Mapping`]
+/// given with [`mapping`](Decoder::mapping), which also tells exactly which
+/// records are wrappers and which properties were required, so that their
+/// `null`s are kept.
+pub struct Decoder<'a> {
+    root: &'a AvroType,
+    /// Named types by full name.
+    named: HashMap<String, &'a AvroType>,
+    mapping: Option<&'a Mapping>,
+}
+
+impl<'a> Decoder<'a> {
+    /// A decoder for the instances of `schema`, which is taken as the
+    /// [`Encoder`](super::Encoder) takes it.
+    pub fn new(schema: &'a AvroType) -> Self {
+        let mut named = HashMap::new();
+        let root = match schema {
+            AvroType::Union(types) => {
+                for t in types {
+                    collect(t, "", &mut named);
+                }
+                types.last().unwrap_or(schema)
+            }
+            other => {
+                collect(other, "", &mut named);
+                other
+            }
+        };
+        Decoder {
+            root,
+            named,
+            mapping: None,
+        }
+    }
+
+    /// Restore original names, and unwrap exactly the wrapper records, as
+    /// recorded by the conversion that produced the schema.
+    pub fn mapping(mut self, mapping: &'a Mapping) -> Self {
+        self.mapping = Some(mapping);
+        self
+    }
+
+    /// Read one value from the front of `input`, advancing past it.
+    pub fn decode(&self, input: &mut &[u8]) -> Result<Value, Error> {
+        self.read(input, self.root, "", "")
+    }
+
+    fn resolve(&self, name: &str, namespace: &str) -> Option<&'a AvroType> {
+        if !name.contains('.') && !namespace.is_empty() {
+            if let Some(t) = self.named.get(&format!("{namespace}.{name}")) {
+                return Some(t);
+            }
+        }
+        self.named.get(name).copied()
+    }
+
+    fn read(
+        &self,
+        input: &mut &[u8],
+        avro_type: &AvroType,
+        namespace: &str,
+        path: &str,
+    ) -> Result<Value, Error> {
+        let truncated = || invalid(path, "unexpected end of data".to_string());
+        match avro_type {
+            AvroType::Reference(name) => match self.resolve(name, namespace) {
+                Some(t) => self.read(input, t, namespace, path),
+                None => Err(invalid(path, format!("undefined type {name}"))),
+            },
+            AvroType::Primitive { r#type, .. } => match r#type.as_str() {
+                "null" => Ok(Value::Null),
+                "boolean" => match take(input, 1).ok_or_else(truncated)? {
+                    [0] => Ok(Value::Bool(false)),
+                    [1] => Ok(Value::Bool(true)),
+                    [b] => Err(invalid(path, format!("invalid boolean {b}"))),
+                    _ => unreachable!(),
+                },
+                "int" | "long" => Ok(read_long(input).ok_or_else(truncated)?.into()),
+                "float" => {
+                    let bytes = take(input, 4).ok_or_else(truncated)?;
+                    let f = f32::from_le_bytes(bytes.try_into().unwrap());
+                    // Widen through the shortest decimal form, so 0.1f32 reads as 0.1
+                    Ok(number(f.to_string().parse().unwrap_or(f as f64)))
+                }
+                "double" => {
+                    let bytes = take(input, 8).ok_or_else(truncated)?;
+                    Ok(number(f64::from_le_bytes(bytes.try_into().unwrap())))
+                }
+                "string" => {
+                    let bytes = read_bytes(input).ok_or_else(truncated)?;
+                    let s = std::str::from_utf8(bytes)
+                        .map_err(|_| invalid(path, "invalid UTF-8 in string".to_string()))?;
+                    Ok(Value::String(s.to_string()))
+                }
+                "bytes" => Ok(base64_encode(read_bytes(input).ok_or_else(truncated)?).into()),
+                other => Err(invalid(path, format!("unknown type {other}"))),
+            },
+            AvroType::Fixed { size, .. } => {
+                let bytes = take(input, *size as usize).ok_or_else(truncated)?;
+                Ok(base64_encode(bytes).into())
+            }
+            AvroType::Enum { name, symbols, .. } => {
+                let index = read_long(input).ok_or_else(truncated)?;
+                let symbol = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| symbols.get(i))
+                    .ok_or_else(|| invalid(path, format!("enum index {index} out of range")))?;
+                let full_name = qualify(name, &record_namespace(avro_type, namespace));
+                Ok(self
+                    .mapping
+                    .and_then(|m| m.symbol(&full_name, symbol))
+                    .or_else(|| avro_type.altsymbol(ALTNAME_PURPOSE, symbol))
+                    .cloned()
+                    .unwrap_or_else(|| Value::String(symbol.clone())))
+            }
+            AvroType::Array { items, .. } => {
+                let mut values = Vec::new();
+                self.read_blocks(input, path, |input| {
+                    let path = format!("{path}/{}", values.len());
+                    values.push(self.read(input, items, namespace, &path)?);
+                    Ok(())
+                })?;
+                Ok(Value::Array(values))
+            }
+            AvroType::Map { values, .. } => {
+                let mut entries = Map::new();
+                self.read_map(input, values, namespace, path, &mut entries)?;
+                Ok(Value::Object(entries))
+            }
+            AvroType::Record { name, fields, .. } => {
+                let ns = record_namespace(avro_type, namespace);
+                let full_name = qualify(name, &ns);
+                let wrapped = match self.mapping.and_then(|m| m.types.get(&full_name)) {
+                    Some(t) => t
+                        .wrapper
+                        .as_ref()
+                        .and_then(|wrapper| fields.iter().find(|f| f.name == *wrapper)),
+                    None => wrapped_field(name, fields),
+                };
+                if let Some(field) = wrapped {
+                    return self.read(input, &field.field_type, &ns, path);
+                }
+                self.read_record(input, &full_name, fields, &ns, path)
+            }
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => {
+                let unit = |suffix: &str| match suffix {
+                    "millis" => 1_000,
+                    "micros" => 1_000_000,
+                    _ => 1_000_000_000,
+                };
+                let bytes = match base.as_ref() {
+                    AvroType::Primitive { r#type, .. } if r#type == "bytes" => {
+                        Some(read_bytes(input).ok_or_else(truncated)?)
+                    }
+                    AvroType::Fixed { size, .. } => {
+                        Some(take(input, *size as usize).ok_or_else(truncated)?)
+                    }
+                    _ => None,
+                };
+                if let Some(bytes) = bytes {
+                    return Ok(match logical_type.as_str() {
+                        "duration" => format_duration(bytes).into(),
+                        "decimal" => {
+                            let scale = attributes.get("scale").and_then(Value::as_u64);
+                            let text = format_decimal(bytes, scale.unwrap_or(0) as usize)
+                                .ok_or_else(|| {
+                                    invalid(path, "decimal does not fit 128 bits".to_string())
+                                })?;
+                            decimal_value(text)
+                        }
+                        _ => base64_encode(bytes).into(),
+                    });
+                }
+                let ns = record_namespace(base, namespace);
+                let value = self.read(input, base, &ns, path)?;
+                let formatted = match (logical_type.as_str(), value.as_i64()) {
+                    ("date", Some(days)) => format_date(days),
+                    (t, Some(n)) if t.starts_with("timestamp-") => {
+                        format!("{}Z", format_timestamp(n, unit(&t[10..])))
+                    }
+                    (t, Some(n)) if t.starts_with("local-timestamp-") => {
+                        format_timestamp(n, unit(&t[16..]))
+                    }
+                    (t, Some(n)) if t.starts_with("time-") => {
+                        format!("{}Z", format_time(n, unit(&t[5..])))
+                    }
+                    // Unknown logical types are read as the base type
+                    _ => return Ok(value),
+                };
+                Ok(Value::String(formatted))
+            }
+            AvroType::Union(branches) => {
+                let index = read_long(input).ok_or_else(truncated)?;
+                let branch = usize::try_from(index)
+                    .ok()
+                    .and_then(|i| branches.get(i))
+                    .ok_or_else(|| invalid(path, format!("union index {index} out of range")))?;
+                self.read(input, branch, namespace, path)
+            }
+        }
+    }
+
+    /// Read the blocks of an array or map, calling `item` for each item.
+    fn read_blocks(
+        &self,
+        input: &mut &[u8],
+        path: &str,
+        mut item: impl FnMut(&mut &[u8]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        let truncated = || invalid(path, "unexpected end of data".to_string());
+        loop {
+            let count = read_long(input).ok_or_else(truncated)?;
+            if count == 0 {
+                return Ok(());
+            }
+            // A negative count is followed by the block's size in bytes
+            if count < 0 {
+                read_long(input).ok_or_else(truncated)?;
+            }
+            for _ in 0..count.unsigned_abs() {
+                item(input)?;
+            }
+        }
+    }
+
+    fn read_map(
+        &self,
+        input: &mut &[u8],
+        values: &AvroType,
+        namespace: &str,
+        path: &str,
+        entries: &mut Map<String, Value>,
+    ) -> Result<(), Error> {
+        self.read_blocks(input, path, |input| {
+            let key = read_bytes(input)
+                .and_then(|key| std::str::from_utf8(key).ok())
+                .ok_or_else(|| invalid(path, "invalid map key".to_string()))?
+                .to_string();
+            let path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+            let value = self.read(input, values, namespace, &path)?;
+            entries.insert(key, value);
+            Ok(())
+        })
+    }
+
+    /// Read a record's fields as the properties of a JSON object.
+    fn read_record(
+        &self,
+        input: &mut &[u8],
+        name: &str,
+        fields: &[AvroField],
+        namespace: &str,
+        path: &str,
+    ) -> Result<Value, Error> {
+        let short_name = name.rsplit('.').next().unwrap_or(name);
+        let mut properties = Map::new();
+        let mut rest = Map::new();
+        for field in fields {
+            match &field.field_type {
+                AvroType::Map { values, .. } if field.name == short_name => {
+                    self.read_map(input, values, namespace, path, &mut rest)?;
+                }
+                field_type => {
+                    let mapped = self.mapping.and_then(|m| m.field(name, &field.name));
+                    let property = mapped.map_or(field.altname(ALTNAME_PURPOSE), |f| &f.name);
+                    let key = property.replace('~', "~0").replace('/', "~1");
+                    let value =
+                        self.read(input, field_type, namespace, &format!("{path}/{key}"))?;
+                    let optional = mapped.map_or_else(|| is_optional(field_type), |f| !f.required);
+                    if value.is_null() && optional {
+                        continue;
+                    }
+                    properties.insert(property.to_string(), value);
+                }
+            }
+        }
+        properties.extend(rest);
+        Ok(Value::Object(properties))
+    }
+}
+
+/// Whether a field's type is the nullable union the converter gives
+/// properties that aren't required.
+fn is_optional(field_type: &AvroType) -> bool {
+    match field_type {
+        AvroType::Union(branches) => branches
+            .iter()
+            .any(|b| matches!(b, AvroType::Primitive { r#type, .. } if r#type == "null")),
+        _ => false,
+    }
+}
+
+fn invalid(path: &str, message: String) -> Error {
+    Error::InvalidData {
+        path: path.to_string(),
+        message,
+    }
+}
+
+/// A JSON number for a float, with integral values as integers and
+/// non-finite ones as `null`.
+fn number(f: f64) -> Value {
+    if f.fract() == 0.0 && f.abs() < (1u64 << 53) as f64 {
+        (f as i64).into()
+    } else {
+        Number::from_f64(f).map_or(Value::Null, Value::Number)
+    }
+}
+
+/// Split `n` bytes off the front of `input`.
+fn take<'i>(input: &mut &'i [u8], n: usize) -> Option<&'i [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (head, tail) = input.split_at(n);
+    *input = tail;
+    Some(head)
+}
+
+/// Read a zig-zag variable-length integer.
+pub(crate) fn read_long(input: &mut &[u8]) -> Option<i64> {
+    let mut z = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *take(input, 1)?.first()?;
+        z |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some((z >> 1) as i64 ^ -((z & 1) as i64));
+        }
+    }
+    None
+}
+
+/// Read length-prefixed bytes, as of `bytes` and `string`.
+pub(crate) fn read_bytes<'i>(input: &mut &'i [u8]) -> Option<&'i [u8]> {
+    let len = usize::try_from(read_long(input)?).ok()?;
+    take(input, len)
+}
+
+/// Standard base64 with padding.
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// The proleptic Gregorian date of a day count since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn format_date(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// `HH:MM:SS` of a time since midnight in `1/unit` seconds, with as many
+/// fractional digits as it needs.
+fn format_time(n: i64, unit: i64) -> String {
+    let seconds = n.div_euclid(unit);
+    let fraction = n.rem_euclid(unit);
+    let mut time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if fraction != 0 {
+        let digits = format!("{fraction:0width$}", width = unit.ilog10() as usize);
+        time.push('.');
+        time.push_str(digits.trim_end_matches('0'));
+    }
+    time
+}
+
+/// `date-time` without an offset of a time since the epoch in `1/unit`
+/// seconds.
+fn format_timestamp(n: i64, unit: i64) -> String {
+    let per_day = 86_400 * unit;
+    format!(
+        "{}T{}",
+        format_date(n.div_euclid(per_day)),
+        format_time(n.rem_euclid(per_day), unit)
+    )
+}
+
+/// ISO 8601 `duration` of little-endian months, days and milliseconds.
+fn format_duration(bytes: &[u8]) -> String {
+    let part = |i: usize| {
+        bytes
+            .get(i * 4..i * 4 + 4)
+            .map_or(0, |b| u32::from_le_bytes(b.try_into().unwrap()) as u64)
+    };
+    let (months, days, millis) = (part(0), part(1), part(2));
+    let mut out = "P".to_string();
+    for (amount, designator) in [(months / 12, 'Y'), (months % 12, 'M'), (days, 'D')] {
+        if amount > 0 {
+            out.push_str(&format!("{amount}{designator}"));
+        }
+    }
+    if millis > 0 || out.len() == 1 {
+        out.push('T');
+        let seconds = millis / 1000;
+        for (amount, designator) in [(seconds / 3600, 'H'), (seconds / 60 % 60, 'M')] {
+            if amount > 0 {
+                out.push_str(&format!("{amount}{designator}"));
+            }
+        }
+        if seconds % 60 > 0 || millis % 1000 > 0 || out.ends_with('T') {
+            out.push_str(&(seconds % 60).to_string());
+            if millis % 1000 > 0 {
+                let fraction = format!(".{:03}", millis % 1000);
+                out.push_str(fraction.trim_end_matches('0'));
+            }
+            out.push('S');
+        }
+    }
+    out
+}
+
+/// Decimal text of a two's complement big-endian unscaled value, if it fits
+/// an `i128`.
+fn format_decimal(bytes: &[u8], scale: usize) -> Option<String> {
+    let negative = bytes.first().is_some_and(|b| b & 0x80 != 0);
+    let unscaled = bytes
+        .iter()
+        .try_fold(if negative { -1i128 } else { 0 }, |n, &b| {
+            Some(n.checked_mul(256)? | b as i128)
+        })?;
+    let digits = format!("{:0width$}", unscaled.unsigned_abs(), width = scale + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - scale);
+    let sign = if negative { "-" } else { "" };
+    Some(if fraction.is_empty() {
+        format!("{sign}{whole}")
+    } else {
+        format!("{sign}{whole}.{fraction}")
+    })
+}
+
+/// A decimal as a JSON number if one holds it exactly, otherwise as its text.
+fn decimal_value(text: String) -> Value {
+    let exact = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &text
+    };
+    if let Ok(i) = exact.parse::<i64>() {
+        return i.into();
+    }
+    let float = exact.parse::<f64>().ok().filter(|f| f.to_string() == exact);
+    match float.and_then(Number::from_f64) {
+        Some(n) => Value::Number(n),
+        None => Value::String(text),
+    }
+}
@@ -0,0 +1,835 @@
+//! Avro binary encoding of JSON instances.
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::avro::{AvroField, AvroType};
+use crate::common::avro_name;
+use crate::converter::structs::ALTNAME_PURPOSE;
+use crate::error::Error;
+
+/// How closely a JSON value must match a type to be written as it.
+///
+/// Union branches are first tried strictly, so that e.g. `3` picks `int` over
+/// an earlier `float` and `"abc"` picks `string` over an earlier `bytes`.
+#[derive(Clone, Copy, PartialEq)]
+enum Fit {
+    /// Only the JSON kinds the converter maps to the type.
+    Strict,
+    /// Also numbers widened to floats, strings as binary, and records with
+    /// properties the schema doesn't know.
+    Lenient,
+}
+
+/// Writes JSON instances in the Avro binary encoding of a schema.
+///
+/// The schema is the one [`jsons_to_avro`](crate::converter::jsons_to_avro)
+/// produced for the JSON Schema the instances are valid against. For a list
+/// of top-level types, instances are of the last one.
+pub struct Encoder<'a> {
+    root: &'a AvroType,
+    /// Named types by full name.
+    named: HashMap<String, &'a AvroType>,
+}
+
+impl<'a> Encoder<'a> {
+    pub fn new(schema: &'a AvroType) -> Self {
+        let mut named = HashMap::new();
+        let root = match schema {
+            AvroType::Union(types) => {
+                for t in types {
+                    collect(t, "", &mut named);
+                }
+                types.last().unwrap_or(schema)
+            }
+            other => {
+                collect(other, "", &mut named);
+                other
+            }
+        };
+        Encoder { root, named }
+    }
+
+    /// Append the encoding of `value` to `out`.
+    ///
+    /// Nothing is appended if the value doesn't fit the schema.
+    pub fn encode(&self, value: &Value, out: &mut Vec<u8>) -> Result<(), Error> {
+        let mut buf = Vec::new();
+        self.write(value, self.root, "", "", Fit::Lenient, &mut buf)?;
+        out.extend(buf);
+        Ok(())
+    }
+
+    fn resolve(&self, name: &str, namespace: &str) -> Option<&'a AvroType> {
+        if !name.contains('.') && !namespace.is_empty() {
+            if let Some(t) = self.named.get(&format!("{namespace}.{name}")) {
+                return Some(t);
+            }
+        }
+        self.named.get(name).copied()
+    }
+
+    fn write(
+        &self,
+        value: &Value,
+        avro_type: &AvroType,
+        namespace: &str,
+        path: &str,
+        fit: Fit,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let mismatch = || {
+            invalid(
+                path,
+                format!("{} does not fit {}", kind(value), describe(avro_type)),
+            )
+        };
+        match avro_type {
+            AvroType::Reference(name) => match self.resolve(name, namespace) {
+                Some(t) => self.write(value, t, namespace, path, fit, out),
+                None => Err(invalid(path, format!("undefined type {name}"))),
+            },
+            AvroType::Primitive { r#type, attributes } => {
+                // Values of a non-string `enum` kept as the allowed values of their type
+                if let Some(allowed) = attributes.get("enum").and_then(Value::as_array) {
+                    if !allowed.iter().any(|v| same_value(v, value)) {
+                        return Err(invalid(
+                            path,
+                            format!("{value} is not one of {}", Value::from(allowed.clone())),
+                        ));
+                    }
+                }
+                match (r#type.as_str(), value) {
+                    ("null", Value::Null) => Ok(()),
+                    ("boolean", Value::Bool(b)) => {
+                        out.push(*b as u8);
+                        Ok(())
+                    }
+                    ("int", Value::Number(n)) => {
+                        match n.as_i64().and_then(|i| i32::try_from(i).ok()) {
+                            Some(i) => {
+                                write_long(out, i as i64);
+                                Ok(())
+                            }
+                            None => Err(mismatch()),
+                        }
+                    }
+                    ("long", Value::Number(n)) => match n.as_i64() {
+                        Some(i) => {
+                            write_long(out, i);
+                            Ok(())
+                        }
+                        None => Err(mismatch()),
+                    },
+                    ("float" | "double", Value::Number(n)) if n.is_f64() || fit == Fit::Lenient => {
+                        let f = n.as_f64().ok_or_else(mismatch)?;
+                        if r#type == "float" {
+                            out.extend((f as f32).to_le_bytes());
+                        } else {
+                            out.extend(f.to_le_bytes());
+                        }
+                        Ok(())
+                    }
+                    ("string", Value::String(s)) => {
+                        write_bytes(out, s.as_bytes());
+                        Ok(())
+                    }
+                    ("bytes", Value::String(s)) => {
+                        let bytes = binary(s, None, fit).ok_or_else(mismatch)?;
+                        write_bytes(out, &bytes);
+                        Ok(())
+                    }
+                    _ => Err(mismatch()),
+                }
+            }
+            AvroType::Fixed { size, .. } => {
+                let bytes = value
+                    .as_str()
+                    .and_then(|s| binary(s, Some(*size as usize), fit))
+                    .ok_or_else(mismatch)?;
+                out.extend(bytes);
+                Ok(())
+            }
+            AvroType::Enum { symbols, .. } => {
+                // The value a symbol stands for, then the symbol or the name it was made from
+                let index = symbols
+                    .iter()
+                    .position(|symbol| {
+                        avro_type
+                            .altsymbol(ALTNAME_PURPOSE, symbol)
+                            .is_some_and(|v| same_value(v, value))
+                    })
+                    .or_else(|| {
+                        let s = value.as_str()?;
+                        symbols
+                            .iter()
+                            .position(|symbol| symbol == s)
+                            .or_else(|| symbols.iter().position(|symbol| *symbol == avro_name(s)))
+                    })
+                    .ok_or_else(|| {
+                        invalid(
+                            path,
+                            format!("{value} is not a symbol of {}", describe(avro_type)),
+                        )
+                    })?;
+                write_long(out, index as i64);
+                Ok(())
+            }
+            AvroType::Array { items, .. } => {
+                let values = value.as_array().ok_or_else(mismatch)?;
+                if !values.is_empty() {
+                    write_long(out, values.len() as i64);
+                    for (i, item) in values.iter().enumerate() {
+                        self.write(item, items, namespace, &format!("{path}/{i}"), fit, out)?;
+                    }
+                }
+                write_long(out, 0);
+                Ok(())
+            }
+            AvroType::Map { values, .. } => {
+                let entries = value.as_object().ok_or_else(mismatch)?;
+                self.write_map(entries.iter(), values, namespace, path, fit, out)
+            }
+            AvroType::Record { name, fields, .. } => {
+                let ns = record_namespace(avro_type, namespace);
+                if let Some(field) = wrapped_field(name, fields) {
+                    return self.write(value, &field.field_type, &ns, path, fit, out);
+                }
+                let properties = value.as_object().ok_or_else(mismatch)?;
+                self.write_record(properties, name, fields, &ns, path, fit, out)
+            }
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => {
+                let ns = record_namespace(base, namespace);
+                match logical(logical_type, base, attributes, value, fit) {
+                    Some(Ok(Logical::Long(n))) => {
+                        write_long(out, n);
+                        Ok(())
+                    }
+                    Some(Ok(Logical::Bytes(bytes))) => {
+                        if let AvroType::Fixed { .. } = base.as_ref() {
+                            out.extend(bytes);
+                        } else {
+                            write_bytes(out, &bytes);
+                        }
+                        Ok(())
+                    }
+                    Some(Err(message)) => Err(invalid(path, message)),
+                    // Unknown logical types and plain values are written as the base type
+                    None => self.write(value, base, &ns, path, fit, out),
+                }
+            }
+            AvroType::Union(branches) => {
+                let passes: &[Fit] = match fit {
+                    Fit::Strict => &[Fit::Strict],
+                    Fit::Lenient => &[Fit::Strict, Fit::Lenient],
+                };
+                for pass in passes {
+                    for (index, branch) in branches.iter().enumerate() {
+                        let mut buf = Vec::new();
+                        if self
+                            .write(value, branch, namespace, path, *pass, &mut buf)
+                            .is_ok()
+                        {
+                            write_long(out, index as i64);
+                            out.extend(buf);
+                            return Ok(());
+                        }
+                    }
+                }
+                Err(invalid(
+                    path,
+                    format!("{} fits no branch of the union", kind(value)),
+                ))
+            }
+        }
+    }
+
+    fn write_map<'v>(
+        &self,
+        entries: impl ExactSizeIterator<Item = (&'v String, &'v Value)>,
+        values: &AvroType,
+        namespace: &str,
+        path: &str,
+        fit: Fit,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        if entries.len() > 0 {
+            write_long(out, entries.len() as i64);
+            for (key, value) in entries {
+                write_bytes(out, key.as_bytes());
+                let path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                self.write(value, values, namespace, &path, fit, out)?;
+            }
+        }
+        write_long(out, 0);
+        Ok(())
+    }
+
+    /// Write the properties of a JSON object as a record's fields.
+    ///
+    /// Fields are matched to properties by name, or by the `avro_name` of the
+    /// property. A map field named after the record holds the properties no
+    /// other field matches (`patternProperties`).
+    fn write_record(
+        &self,
+        properties: &Map<String, Value>,
+        name: &str,
+        fields: &[AvroField],
+        namespace: &str,
+        path: &str,
+        fit: Fit,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let property = |field: &AvroField| {
+            properties.get_key_value(&field.name).or_else(|| {
+                properties
+                    .iter()
+                    .find(|(key, _)| avro_name(key) == field.name)
+            })
+        };
+        let short_name = name.rsplit('.').next().unwrap_or(name);
+        let is_rest = |field: &AvroField| {
+            field.name == short_name && matches!(field.field_type, AvroType::Map { .. })
+        };
+        let matched: Vec<&String> = fields
+            .iter()
+            .filter(|field| !is_rest(field))
+            .filter_map(|field| property(field).map(|(key, _)| key))
+            .collect();
+        let rest: Vec<(&String, &Value)> = properties
+            .iter()
+            .filter(|(key, _)| !matched.contains(key))
+            .collect();
+        let has_rest = fields.iter().any(is_rest);
+        if fit == Fit::Strict && !has_rest {
+            if let Some((key, _)) = rest.first() {
+                return Err(invalid(path, format!("unknown property {key:?}")));
+            }
+        }
+
+        for field in fields {
+            if let (true, AvroType::Map { values, .. }) = (is_rest(field), &field.field_type) {
+                self.write_map(rest.iter().copied(), values, namespace, path, fit, out)?;
+                continue;
+            }
+            match (property(field), &field.default) {
+                (Some((key, value)), _) => {
+                    let path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                    self.write(value, &field.field_type, namespace, &path, fit, out)?;
+                }
+                (None, Some(default)) => {
+                    self.write(default, &field.field_type, namespace, path, fit, out)?
+                }
+                (None, None) => {
+                    self.write(&Value::Null, &field.field_type, namespace, path, fit, out)
+                        .map_err(|_| invalid(path, format!("missing property {:?}", field.name)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Register the named types of `avro_type` by full name.
+pub(crate) fn collect<'a>(
+    avro_type: &'a AvroType,
+    namespace: &str,
+    named: &mut HashMap<String, &'a AvroType>,
+) {
+    match avro_type {
+        AvroType::Record { fields, .. } => {
+            let ns = record_namespace(avro_type, namespace);
+            named.insert(
+                qualify(avro_type.name().unwrap_or_default(), &ns),
+                avro_type,
+            );
+            for field in fields {
+                collect(&field.field_type, &ns, named);
+            }
+        }
+        AvroType::Enum { .. } | AvroType::Fixed { .. } => {
+            let ns = record_namespace(avro_type, namespace);
+            named.insert(
+                qualify(avro_type.name().unwrap_or_default(), &ns),
+                avro_type,
+            );
+        }
+        AvroType::Logical { base, .. } if base.name().is_some() => {
+            let ns = record_namespace(base, namespace);
+            named.insert(qualify(base.name().unwrap_or_default(), &ns), avro_type);
+        }
+        AvroType::Array { items: inner, .. } | AvroType::Map { values: inner, .. } => {
+            collect(inner, namespace, named)
+        }
+        AvroType::Union(types) => types.iter().for_each(|t| collect(t, namespace, named)),
+        _ => {}
+    }
+}
+
+pub(crate) fn qualify(name: &str, namespace: &str) -> String {
+    let short = name.rsplit('.').next().unwrap_or(name);
+    if namespace.is_empty() {
+        short.to_string()
+    } else {
+        format!("{namespace}.{short}")
+    }
+}
+
+/// Namespace a named type defines its children in.
+pub(crate) fn record_namespace(avro_type: &AvroType, parent: &str) -> String {
+    match avro_type {
+        AvroType::Record {
+            name, namespace, ..
+        }
+        | AvroType::Enum {
+            name, namespace, ..
+        }
+        | AvroType::Fixed {
+            name, namespace, ..
+        } => match name.rsplit_once('.') {
+            Some((ns, _)) => ns.to_string(),
+            None => namespace.clone().unwrap_or_else(|| parent.to_string()),
+        },
+        _ => parent.to_string(),
+    }
+}
+
+/// The field of a record the converter introduced to wrap a non-record
+/// value: the `items` of a root array's `*_wrapper`, the `options` of a
+/// `*_union`, or the single field named after its record.
+pub(crate) fn wrapped_field<'f>(name: &str, fields: &'f [AvroField]) -> Option<&'f AvroField> {
+    let short_name = name.rsplit('.').next().unwrap_or(name);
+    match fields {
+        [field]
+            if short_name.ends_with("_wrapper")
+                || short_name.ends_with("_union")
+                || (field.name == short_name
+                    && !matches!(field.field_type, AvroType::Map { .. })) =>
+        {
+            Some(field)
+        }
+        _ => None,
+    }
+}
+
+/// Whether two JSON values are equal, taking numbers by value (`1` is `1.0`).
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn invalid(path: &str, message: String) -> Error {
+    Error::InvalidData {
+        path: path.to_string(),
+        message,
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(n) if n.is_f64() => "a number",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn describe(avro_type: &AvroType) -> String {
+    match avro_type {
+        AvroType::Primitive { r#type, .. } => r#type.clone(),
+        AvroType::Record { name, .. } => format!("record {name}"),
+        AvroType::Enum { name, .. } => format!("enum {name}"),
+        AvroType::Fixed { name, size, .. } => format!("fixed {name} of size {size}"),
+        AvroType::Array { .. } => "array".to_string(),
+        AvroType::Map { .. } => "map".to_string(),
+        AvroType::Logical { logical_type, .. } => logical_type.clone(),
+        AvroType::Union(_) => "union".to_string(),
+        AvroType::Reference(name) => name.clone(),
+    }
+}
+
+/// Append `n` as a zig-zag variable-length integer.
+pub(crate) fn write_long(out: &mut Vec<u8>, n: i64) {
+    let mut z = ((n << 1) ^ (n >> 63)) as u64;
+    while z >= 0x80 {
+        out.push((z as u8) | 0x80);
+        z >>= 7;
+    }
+    out.push(z as u8);
+}
+
+/// Append length-prefixed bytes, as for `bytes` and `string`.
+pub(crate) fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(out, bytes.len() as i64);
+    out.extend_from_slice(bytes);
+}
+
+/// The octets of a binary string: base64 text (`contentEncoding: base64`,
+/// `format: byte`), else raw octets as code points up to 255 (`format:
+/// binary`). A fixed `size` takes raw octets when the string has exactly that
+/// many characters.
+///
+/// Any string might be base64, so binary only fits leniently.
+fn binary(s: &str, size: Option<usize>, fit: Fit) -> Option<Vec<u8>> {
+    if fit == Fit::Strict {
+        return None;
+    }
+    let raw = || {
+        s.chars()
+            .map(|c| u8::try_from(c as u32).ok())
+            .collect::<Option<Vec<u8>>>()
+    };
+    let bytes = match size {
+        Some(size) if s.chars().count() == size => raw(),
+        _ => base64_decode(s).or_else(raw),
+    }?;
+    match size {
+        Some(size) if bytes.len() != size => None,
+        _ => Some(bytes),
+    }
+}
+
+/// Decode standard or URL-safe base64, padded or not.
+pub(crate) fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let digits = s.trim_end_matches('=');
+    if digits.len() % 4 == 1 || s.len() - digits.len() > 2 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in digits.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// The underlying value of a logical type.
+enum Logical {
+    /// `int` and `long` based types.
+    Long(i64),
+    /// `bytes` and `fixed` based types.
+    Bytes(Vec<u8>),
+}
+
+/// Convert the JSON form of a logical type's value, or `None` to write the
+/// value as the base type.
+fn logical(
+    logical_type: &str,
+    base: &AvroType,
+    attributes: &Map<String, Value>,
+    value: &Value,
+    fit: Fit,
+) -> Option<Result<Logical, String>> {
+    let unit = |suffix: &str| match suffix {
+        "millis" => 1_000,
+        "micros" => 1_000_000,
+        _ => 1_000_000_000,
+    };
+    let text = value.as_str();
+    match logical_type {
+        "date" => Some(text?.parse_date().map(Logical::Long)),
+        t if t.starts_with("timestamp-") || t.starts_with("local-timestamp-") => {
+            let (_, suffix) = t.rsplit_once('-')?;
+            let local = t.starts_with("local-");
+            Some(
+                text?
+                    .parse_timestamp(unit(suffix), local)
+                    .map(Logical::Long),
+            )
+        }
+        t if t.starts_with("time-") => {
+            let (_, suffix) = t.rsplit_once('-')?;
+            Some(text?.parse_time(unit(suffix)).map(Logical::Long))
+        }
+        "duration" => Some(text?.parse_duration().map(Logical::Bytes)),
+        "uuid" if fit == Fit::Strict && !text.is_some_and(is_uuid) => {
+            Some(Err("not a UUID".to_string()))
+        }
+        "decimal" => {
+            let number = match value {
+                Value::Number(n) => n.to_string(),
+                Value::String(s) if fit == Fit::Lenient => s.clone(),
+                _ => return Some(Err("not a number".to_string())),
+            };
+            let param = |key: &str| attributes.get(key).and_then(Value::as_u64).unwrap_or(0) as u32;
+            let size = match base {
+                AvroType::Fixed { size, .. } => Some(*size as usize),
+                _ => None,
+            };
+            Some(decimal(&number, param("precision"), param("scale"), size).map(Logical::Bytes))
+        }
+        _ => None,
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.iter().map(|g| g.len()).eq([8, 4, 4, 4, 12])
+        && groups
+            .iter()
+            .all(|g| g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Two's complement big-endian bytes of a decimal's unscaled value, in
+/// `size` bytes for a `fixed` or as few as possible for `bytes`.
+fn decimal(
+    number: &str,
+    precision: u32,
+    scale: u32,
+    size: Option<usize>,
+) -> Result<Vec<u8>, String> {
+    let out_of_range = || format!("{number} does not fit decimal({precision}, {scale})");
+    let text = number.trim().to_ascii_lowercase();
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((m, e)) => (m, e.parse::<i64>().map_err(|_| out_of_range())?),
+        None => (text.as_str(), 0),
+    };
+    let (negative, mantissa) = match mantissa.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{whole}{fraction}");
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("{number} is not a number"));
+    }
+    // Position of the decimal point, counted in digits after it
+    let shift = (scale as i64)
+        .checked_add(exponent)
+        .and_then(|shift| shift.checked_sub(fraction.len() as i64))
+        .ok_or_else(out_of_range)?;
+    let digits = if shift >= 0 {
+        // Check the width before padding, as the exponent can be huge
+        let significant = digits.trim_start_matches('0');
+        if significant.is_empty() {
+            String::new()
+        } else if significant.len() as u64 + shift as u64 > precision as u64 {
+            return Err(out_of_range());
+        } else {
+            format!("{significant}{}", "0".repeat(shift as usize))
+        }
+    } else {
+        let keep = digits
+            .len()
+            .saturating_sub(usize::try_from(shift.unsigned_abs()).unwrap_or(usize::MAX));
+        if digits[keep..].bytes().any(|b| b != b'0') {
+            return Err(format!("{number} has more than {scale} decimal places"));
+        }
+        digits[..keep].to_string()
+    };
+    let digits = digits.trim_start_matches('0');
+    if digits.len() > precision as usize {
+        return Err(out_of_range());
+    }
+    let unscaled = match digits.parse::<i128>() {
+        Ok(v) if negative => -v,
+        Ok(v) => v,
+        Err(_) if digits.is_empty() => 0,
+        Err(_) => return Err(out_of_range()),
+    };
+    let bytes = unscaled.to_be_bytes();
+    // Drop leading bytes that only repeat the sign
+    let sign = if unscaled < 0 { 0xff } else { 0x00 };
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && bytes[start] == sign
+        && (bytes[start + 1] & 0x80) == (sign & 0x80)
+    {
+        start += 1;
+    }
+    let minimal = &bytes[start..];
+    match size {
+        None => Ok(minimal.to_vec()),
+        Some(size) if minimal.len() <= size => {
+            let mut fixed = vec![sign; size - minimal.len()];
+            fixed.extend_from_slice(minimal);
+            Ok(fixed)
+        }
+        Some(_) => Err(out_of_range()),
+    }
+}
+
+/// Parsing of the RFC 3339 and ISO 8601 forms of JSON Schema's formats.
+trait Temporal {
+    /// Days since the Unix epoch of a `full-date`.
+    fn parse_date(&self) -> Result<i64, String>;
+    /// Time since midnight in `1/unit` seconds of a `full-time` (in UTC when
+    /// it has an offset).
+    fn parse_time(&self, unit: i64) -> Result<i64, String>;
+    /// Time since the Unix epoch in `1/unit` seconds of a `date-time`; a
+    /// `local` timestamp keeps its wall-clock time, ignoring any offset.
+    fn parse_timestamp(&self, unit: i64, local: bool) -> Result<i64, String>;
+    /// Months, days and milliseconds of a `duration`, as three little-endian
+    /// unsigned 32-bit integers.
+    fn parse_duration(&self) -> Result<Vec<u8>, String>;
+}
+
+fn number<T: std::str::FromStr>(s: &str, what: &str) -> Result<T, String> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid {what} {s:?}"));
+    }
+    s.parse().map_err(|_| format!("invalid {what} {s:?}"))
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Split a `full-time` into its time and its offset from UTC in seconds.
+fn split_offset(s: &str) -> Result<(&str, i64), String> {
+    if let Some(time) = s.strip_suffix(['Z', 'z']) {
+        return Ok((time, 0));
+    }
+    match s.rfind(['+', '-']) {
+        Some(i) => {
+            let (time, offset) = s.split_at(i);
+            let (hours, minutes) = offset[1..]
+                .split_once(':')
+                .ok_or_else(|| format!("invalid offset {offset:?}"))?;
+            let seconds =
+                number::<i64>(hours, "offset")? * 3600 + number::<i64>(minutes, "offset")? * 60;
+            Ok((
+                time,
+                if offset.starts_with('-') {
+                    -seconds
+                } else {
+                    seconds
+                },
+            ))
+        }
+        None => Ok((s, 0)),
+    }
+}
+
+impl Temporal for str {
+    fn parse_date(&self) -> Result<i64, String> {
+        let mut parts = self.splitn(3, '-');
+        let (Some(y), Some(m), Some(d)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("invalid date {self:?}"));
+        };
+        let (year, month, day) = (
+            number::<i64>(y, "year")?,
+            number::<i64>(m, "month")?,
+            number::<i64>(d, "day")?,
+        );
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(format!("invalid date {self:?}"));
+        }
+        Ok(days_from_civil(year, month, day))
+    }
+
+    fn parse_time(&self, unit: i64) -> Result<i64, String> {
+        let (time, offset) = split_offset(self)?;
+        let (seconds, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let mut parts = seconds.splitn(3, ':');
+        let (Some(h), Some(m), Some(s)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("invalid time {self:?}"));
+        };
+        let (h, m, s) = (
+            number::<i64>(h, "hour")?,
+            number::<i64>(m, "minute")?,
+            number::<i64>(s, "second")?,
+        );
+        if h > 23 || m > 59 || s > 60 {
+            return Err(format!("invalid time {self:?}"));
+        }
+        let digits = unit.ilog10() as usize;
+        let fraction: String = fraction
+            .chars()
+            .chain(std::iter::repeat('0'))
+            .take(digits)
+            .collect();
+        let fraction = if digits == 0 {
+            0
+        } else {
+            number::<i64>(&fraction, "fraction")?
+        };
+        let seconds = (h * 3600 + m * 60 + s - offset).rem_euclid(86_400);
+        Ok(seconds * unit + fraction)
+    }
+
+    fn parse_timestamp(&self, unit: i64, local: bool) -> Result<i64, String> {
+        let invalid = || format!("invalid date-time {self:?}");
+        let (date, time) = self.split_once(['T', 't', ' ']).ok_or_else(invalid)?;
+        let days = date.parse_date().map_err(|_| invalid())?;
+        let (wall, offset) = split_offset(time).map_err(|_| invalid())?;
+        let since_midnight = wall.parse_time(unit).map_err(|_| invalid())?;
+        let offset = if local { 0 } else { offset };
+        days.checked_mul(86_400 * unit)
+            .and_then(|t| t.checked_add(since_midnight))
+            .and_then(|t| t.checked_sub(offset * unit))
+            .ok_or_else(|| format!("date-time {self:?} is out of range"))
+    }
+
+    fn parse_duration(&self) -> Result<Vec<u8>, String> {
+        let invalid = || format!("invalid duration {self:?}");
+        let body = self.strip_prefix('P').ok_or_else(invalid)?;
+        let (date, time) = body.split_once('T').unwrap_or((body, ""));
+        let (mut months, mut days, mut millis) = (0u64, 0u64, 0u64);
+        for (part, is_time) in [(date, false), (time, true)] {
+            let mut rest = part;
+            while !rest.is_empty() {
+                let end = rest
+                    .find(|c: char| c.is_ascii_alphabetic())
+                    .ok_or_else(invalid)?;
+                let (amount, designator) = (&rest[..end], &rest[end..end + 1]);
+                rest = &rest[end + 1..];
+                let (whole, fraction) = amount.split_once(['.', ',']).unwrap_or((amount, ""));
+                let whole: u64 = number(whole, "duration")?;
+                match (designator, is_time) {
+                    ("S", true) => {
+                        let fraction: String = fraction
+                            .chars()
+                            .chain(std::iter::repeat('0'))
+                            .take(3)
+                            .collect();
+                        millis += whole * 1000 + number::<u64>(&fraction, "duration")?;
+                        continue;
+                    }
+                    _ if !fraction.is_empty() => return Err(invalid()),
+                    ("Y", false) => months += whole * 12,
+                    ("M", false) => months += whole,
+                    ("W", false) => days += whole * 7,
+                    ("D", false) => days += whole,
+                    ("H", true) => millis += whole * 3_600_000,
+                    ("M", true) => millis += whole * 60_000,
+                    _ => return Err(invalid()),
+                }
+            }
+        }
+        let mut out = Vec::with_capacity(12);
+        for n in [months, days, millis] {
+            let n = u32::try_from(n).map_err(|_| format!("duration {self:?} is out of range"))?;
+            out.extend(n.to_le_bytes());
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,414 @@
+//! Avro object container files.
+//!
+//! A file starts with a header holding the schema and codec, followed by
+//! blocks of encoded values, each compressed with the codec and ended by the
+//! file's sync marker.
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+
+use super::decode::{read_bytes, read_long};
+use super::encode::{collect, qualify, record_namespace, write_bytes, write_long};
+use super::{Codec, Decoder, Encoder};
+use crate::avro::AvroType;
+use crate::converter::mapping::Mapping;
+use crate::error::Error;
+
+const MAGIC: &[u8; 4] = b"Obj\x01";
+
+/// Uncompressed block size at which a block is written out.
+pub const DEFAULT_SYNC_INTERVAL: usize = 64_000;
+
+/// Most values a file may hold of a schema whose values take no bytes (like
+/// `"null"`), as the blocks' lengths can't bound their counts.
+const MAX_EMPTY_VALUES: usize = 1 << 20;
+
+/// Writes JSON instances to an object container file in memory.
+///
+/// The header is written before the first block, and the last block by
+/// [`finish`](Writer::finish).
+pub struct Writer<'a> {
+    out: Vec<u8>,
+    encoder: Encoder<'a>,
+    /// The standalone root schema written in the header.
+    schema: AvroType,
+    codec: Codec,
+    sync_interval: usize,
+    sync: [u8; 16],
+    header_written: bool,
+    block: Vec<u8>,
+    count: i64,
+}
+
+impl<'a> Writer<'a> {
+    /// A writer of instances of `schema`, taken as the [`Encoder`] takes it.
+    pub fn new(schema: &'a AvroType, codec: Codec) -> Self {
+        Writer {
+            out: Vec::new(),
+            encoder: Encoder::new(schema),
+            schema: standalone(schema),
+            codec,
+            sync_interval: DEFAULT_SYNC_INTERVAL,
+            sync: sync_marker(),
+            header_written: false,
+            block: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Set the uncompressed size in bytes a block grows to before it is
+    /// written (at least one value per block).
+    pub fn sync_interval(mut self, bytes: usize) -> Self {
+        self.sync_interval = bytes;
+        self
+    }
+
+    /// Encode `value` into the current block.
+    pub fn append(&mut self, value: &Value) -> Result<(), Error> {
+        self.encoder.encode(value, &mut self.block)?;
+        self.count += 1;
+        if self.block.len() >= self.sync_interval {
+            self.flush_block();
+        }
+        Ok(())
+    }
+
+    /// Write any pending block and return the file's bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.flush_block();
+        self.out
+    }
+
+    fn write_header(&mut self) {
+        let mut header = MAGIC.to_vec();
+        let schema = Value::from(self.schema.clone()).to_string();
+        write_long(&mut header, 2);
+        for (key, value) in [
+            ("avro.schema", schema),
+            ("avro.codec", self.codec.to_string()),
+        ] {
+            write_bytes(&mut header, key.as_bytes());
+            write_bytes(&mut header, value.as_bytes());
+        }
+        write_long(&mut header, 0);
+        header.extend(self.sync);
+        self.out.extend(header);
+        self.header_written = true;
+    }
+
+    fn flush_block(&mut self) {
+        if !self.header_written {
+            self.write_header();
+        }
+        if self.count == 0 {
+            return;
+        }
+        let data = self.codec.compress(&self.block);
+        let mut block = Vec::with_capacity(data.len() + 32);
+        write_long(&mut block, self.count);
+        write_bytes(&mut block, &data);
+        block.extend(self.sync);
+        self.out.extend(block);
+        self.block.clear();
+        self.count = 0;
+    }
+}
+
+/// The contents of an object container file.
+pub struct Container {
+    /// The schema from the header.
+    pub schema: AvroType,
+    pub codec: Codec,
+    /// Metadata other than the schema and codec.
+    pub metadata: HashMap<String, Vec<u8>>,
+    /// Decompressed blocks with their value counts.
+    blocks: Vec<(usize, Vec<u8>)>,
+}
+
+impl Container {
+    /// Read the header and blocks of a file, decompressing the blocks.
+    pub fn read(bytes: &[u8]) -> Result<Self, Error> {
+        let mut input = bytes;
+        let truncated = || invalid("unexpected end of file");
+        if !input.starts_with(MAGIC) {
+            return Err(invalid("not an Avro object container file"));
+        }
+        input = &input[MAGIC.len()..];
+
+        let mut metadata = HashMap::new();
+        loop {
+            let count = read_long(&mut input).ok_or_else(truncated)?;
+            if count == 0 {
+                break;
+            }
+            if count < 0 {
+                read_long(&mut input).ok_or_else(truncated)?;
+            }
+            for _ in 0..count.unsigned_abs() {
+                let key = read_bytes(&mut input).ok_or_else(truncated)?;
+                let value = read_bytes(&mut input).ok_or_else(truncated)?;
+                let key = String::from_utf8(key.to_vec())
+                    .map_err(|_| invalid("metadata key is not UTF-8"))?;
+                metadata.insert(key, value.to_vec());
+            }
+        }
+        let schema = metadata
+            .remove("avro.schema")
+            .ok_or_else(|| invalid("header has no avro.schema"))?;
+        let schema: Value = serde_json::from_slice(&schema).map_err(|source| Error::Json {
+            uri: "avro.schema".to_string(),
+            source,
+        })?;
+        let schema = AvroType::try_from(schema)
+            .map_err(|e| invalid(&format!("invalid avro.schema: {e}")))?;
+        let codec = match metadata.remove("avro.codec") {
+            None => Codec::Null,
+            Some(name) => {
+                let name = String::from_utf8_lossy(&name);
+                Codec::from_name(&name)
+                    .ok_or_else(|| invalid(&format!("unsupported codec {name:?}")))?
+            }
+        };
+        let sync = input.get(..16).ok_or_else(truncated)?;
+        input = &input[16..];
+
+        // Each value takes at least a byte, unless none of them takes any
+        let empty = takes_no_bytes(&schema, "", &named_types(&schema), &mut HashSet::new());
+        let mut empty_values = 0;
+        let mut blocks = Vec::new();
+        while !input.is_empty() {
+            let count = read_long(&mut input)
+                .and_then(|n| usize::try_from(n).ok())
+                .ok_or_else(truncated)?;
+            let data = read_bytes(&mut input).ok_or_else(truncated)?;
+            if input.get(..16) != Some(sync) {
+                return Err(invalid("block does not end with the sync marker"));
+            }
+            input = &input[16..];
+            let data = codec.decompress(data)?;
+            let too_many = if empty {
+                empty_values += count;
+                empty_values > MAX_EMPTY_VALUES
+            } else {
+                count > data.len()
+            };
+            if too_many {
+                return Err(invalid(&format!(
+                    "block claims {count} values in {} bytes",
+                    data.len()
+                )));
+            }
+            blocks.push((count, data));
+        }
+        Ok(Container {
+            schema,
+            codec,
+            metadata,
+            blocks,
+        })
+    }
+
+    /// Decode the values of every block.
+    pub fn values(&self) -> Result<Vec<Value>, Error> {
+        self.decode(None)
+    }
+
+    /// Decode the values of every block, restoring the JSON names recorded in
+    /// the conversion's `mapping` (see [`Decoder::mapping`]).
+    pub fn mapped_values(&self, mapping: &Mapping) -> Result<Vec<Value>, Error> {
+        self.decode(Some(mapping))
+    }
+
+    fn decode(&self, mapping: Option<&Mapping>) -> Result<Vec<Value>, Error> {
+        // The header holds the root alone, which may itself be a union
+        let schema = AvroType::Union(vec![self.schema.clone()]);
+        let mut decoder = Decoder::new(&schema);
+        if let Some(mapping) = mapping {
+            decoder = decoder.mapping(mapping);
+        }
+        let mut values = Vec::new();
+        for (count, block) in &self.blocks {
+            let mut input = block.as_slice();
+            for _ in 0..*count {
+                values.push(decoder.decode(&mut input)?);
+            }
+            if !input.is_empty() {
+                return Err(invalid("block has bytes left after its values"));
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::InvalidContainer {
+        message: message.to_string(),
+    }
+}
+
+/// Random bytes to end each block with.
+fn sync_marker() -> [u8; 16] {
+    let state = RandomState::new();
+    let mut sync = [0; 16];
+    for (i, chunk) in sync.chunks_mut(8).enumerate() {
+        let mut hasher = state.build_hasher();
+        hasher.write_usize(i);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    sync
+}
+
+/// Named types of a standalone schema by full name.
+fn named_types(schema: &AvroType) -> HashMap<String, &AvroType> {
+    let mut named = HashMap::new();
+    collect(schema, "", &mut named);
+    named
+}
+
+/// Whether the values of `avro_type` encode to no bytes, as `null`, `fixed`
+/// types of size 0 and records of only such fields do.
+fn takes_no_bytes(
+    avro_type: &AvroType,
+    namespace: &str,
+    named: &HashMap<String, &AvroType>,
+    visiting: &mut HashSet<String>,
+) -> bool {
+    match avro_type {
+        AvroType::Primitive { r#type, .. } => r#type == "null",
+        AvroType::Fixed { size, .. } => *size == 0,
+        AvroType::Logical { base, .. } => takes_no_bytes(base, namespace, named, visiting),
+        AvroType::Record { fields, .. } => {
+            let ns = record_namespace(avro_type, namespace);
+            let full_name = qualify(avro_type.name().unwrap_or_default(), &ns);
+            // A record holding itself has no finite values to speak of
+            if !visiting.insert(full_name.clone()) {
+                return false;
+            }
+            let empty = fields
+                .iter()
+                .all(|field| takes_no_bytes(&field.field_type, &ns, named, visiting));
+            visiting.remove(&full_name);
+            empty
+        }
+        AvroType::Reference(name) => [qualify(name, namespace), name.clone()]
+            .into_iter()
+            .find(|n| named.contains_key(n))
+            .is_some_and(|full_name| {
+                let ns = full_name.rsplit_once('.').map_or("", |(ns, _)| ns);
+                takes_no_bytes(named[&full_name], ns, named, visiting)
+            }),
+        AvroType::Enum { .. }
+        | AvroType::Array { .. }
+        | AvroType::Map { .. }
+        | AvroType::Union(_) => false,
+    }
+}
+
+/// The root of a list of top-level types as a single schema, with each type
+/// it refers to defined where it is first used.
+fn standalone(schema: &AvroType) -> AvroType {
+    let AvroType::Union(types) = schema else {
+        return schema.clone();
+    };
+    let Some(root) = types.last() else {
+        return schema.clone();
+    };
+    let mut named = HashMap::new();
+    for t in types {
+        collect(t, "", &mut named);
+    }
+    let mut inliner = Inliner {
+        named,
+        defined: HashSet::new(),
+    };
+    inliner.inline(root, "")
+}
+
+struct Inliner<'a> {
+    /// Named types by full name, wherever they are defined.
+    named: HashMap<String, &'a AvroType>,
+    /// Full names of the named types written so far.
+    defined: HashSet<String>,
+}
+
+impl Inliner<'_> {
+    fn inline(&mut self, avro_type: &AvroType, namespace: &str) -> AvroType {
+        match avro_type {
+            AvroType::Reference(name) => {
+                let full_name = [qualify(name, namespace), name.clone()]
+                    .into_iter()
+                    .find(|n| self.named.contains_key(n));
+                match full_name {
+                    Some(full_name) if !self.defined.contains(&full_name) => {
+                        let definition = self.named[&full_name];
+                        let ns = full_name.rsplit_once('.').map_or("", |(ns, _)| ns);
+                        let mut inlined = self.inline(definition, ns);
+                        // Keep its namespace from being taken from where it is used
+                        set_namespace(&mut inlined, ns);
+                        inlined
+                    }
+                    _ => avro_type.clone(),
+                }
+            }
+            AvroType::Record { .. } | AvroType::Enum { .. } | AvroType::Fixed { .. } => {
+                let ns = record_namespace(avro_type, namespace);
+                let full_name = qualify(avro_type.name().unwrap_or_default(), &ns);
+                // Written already, where an earlier reference to it was
+                if !self.defined.insert(full_name.clone()) {
+                    return AvroType::Reference(full_name);
+                }
+                let mut inlined = avro_type.clone();
+                if let AvroType::Record { fields, .. } = &mut inlined {
+                    for field in fields {
+                        field.field_type = self.inline(&field.field_type, &ns);
+                    }
+                }
+                inlined
+            }
+            AvroType::Logical {
+                logical_type,
+                base,
+                attributes,
+            } => match self.inline(base, namespace) {
+                reference @ AvroType::Reference(_) => reference,
+                base => AvroType::Logical {
+                    logical_type: logical_type.clone(),
+                    base: Box::new(base),
+                    attributes: attributes.clone(),
+                },
+            },
+            AvroType::Array { items, attributes } => AvroType::Array {
+                items: Box::new(self.inline(items, namespace)),
+                attributes: attributes.clone(),
+            },
+            AvroType::Map { values, attributes } => AvroType::Map {
+                values: Box::new(self.inline(values, namespace)),
+                attributes: attributes.clone(),
+            },
+            AvroType::Union(branches) => AvroType::Union(
+                branches
+                    .iter()
+                    .map(|branch| self.inline(branch, namespace))
+                    .collect(),
+            ),
+            AvroType::Primitive { .. } => avro_type.clone(),
+        }
+    }
+}
+
+fn set_namespace(avro_type: &mut AvroType, ns: &str) {
+    match avro_type {
+        AvroType::Record {
+            name, namespace, ..
+        }
+        | AvroType::Enum {
+            name, namespace, ..
+        }
+        | AvroType::Fixed {
+            name, namespace, ..
+        } if !name.contains('.') => *namespace = Some(ns.to_string()),
+        AvroType::Logical { base, .. } => set_namespace(base, ns),
+        _ => {}
+    }
+}
@@ -1,5 +1,7 @@
 use serde_json::Value;
 
+use crate::converter::diagnostics::Diagnostics;
+
 /// Recursively adjust resolved dependencies so records are defined before use.
 pub fn adjust_resolved_dependencies(avro_schema: &mut Value) {
     struct TreeWalker {
@@ -141,6 +143,29 @@ pub fn adjust_resolved_dependencies(avro_schema: &mut Value) {
 
 /// Inline all dependent records to break circular dependencies.
 pub fn inline_dependencies_of(avro_schema: &mut Vec<Value>, record: &mut Value) {
+    inline_nested_dependencies(avro_schema, record, &mut Vec::new());
+    adjust_resolved_dependencies(record);
+}
+
+/// Inline the dependencies of `record`, and of each inlined dependency in turn.
+///
+/// Types already being inlined further up (`visiting`) stay as name references,
+/// which is how Avro expresses recursion.
+fn inline_nested_dependencies(
+    avro_schema: &mut Vec<Value>,
+    record: &mut Value,
+    visiting: &mut Vec<String>,
+) {
+    let own_name = match (
+        record.get("namespace").and_then(|n| n.as_str()),
+        record.get("name").and_then(|n| n.as_str()),
+    ) {
+        (Some(ns), Some(name)) => format!("{}.{}", ns, name),
+        (None, Some(name)) => name.to_string(),
+        _ => String::new(),
+    };
+    visiting.push(own_name);
+
     if let Some(deps) = record.get("dependencies").and_then(|d| d.as_array()) {
         let deps_copy: Vec<String> = deps
             .iter()
@@ -148,6 +173,9 @@ pub fn inline_dependencies_of(avro_schema: &mut Vec<Value>, record: &mut Value)
             .collect();
 
         for dependency in deps_copy {
+            if visiting.contains(&dependency) {
+                continue;
+            }
             if let Some(dep_type) = avro_schema.iter().find(|x| {
                 x.get("name").and_then(|n| n.as_str()) == Some(dependency.as_str())
                     || x.get("namespace")
@@ -155,7 +183,8 @@ pub fn inline_dependencies_of(avro_schema: &mut Vec<Value>, record: &mut Value)
                         .map(|ns| format!("{}.{}", ns, x.get("name").unwrap().as_str().unwrap()))
                         == Some(dependency.clone())
             }) {
-                let dep_clone = dep_type.clone();
+                let mut dep_clone = dep_type.clone();
+                inline_nested_dependencies(avro_schema, &mut dep_clone, visiting);
                 if let Some(fields) = record.get_mut("fields").and_then(|f| f.as_array_mut()) {
                     for field in fields.iter_mut() {
                         swap_dependency_type(avro_schema, field, &dependency, &dep_clone);
@@ -167,16 +196,27 @@ pub fn inline_dependencies_of(avro_schema: &mut Vec<Value>, record: &mut Value)
     if record.get("dependencies").is_some() {
         record.as_object_mut().unwrap().remove("dependencies");
     }
-    adjust_resolved_dependencies(record);
+    visiting.pop();
 }
 
 /// Sort messages by dependencies, inlining when needed.
-pub fn sort_messages_by_dependencies(avro_schema: &mut Vec<Value>) -> Vec<Value> {
+///
+/// Records are emitted once everything they depend on has been defined, either
+/// at the top level or nested inside an earlier record. A record may depend on
+/// itself (Avro resolves recursive names), but a cycle between records is
+/// broken by inlining the others into one of them, after which they are
+/// defined and no longer emitted separately. Records whose dependencies can
+/// never be satisfied are dropped with a warning in `diagnostics`.
+pub fn sort_messages_by_dependencies(
+    avro_schema: &mut Vec<Value>,
+    diagnostics: &mut Diagnostics,
+) -> Vec<Value> {
     if avro_schema.iter().all(|r| r.is_string()) {
         return avro_schema.clone();
     }
 
     let mut sorted_messages: Vec<Value> = Vec::new();
+    let mut defined: Vec<String> = Vec::new();
 
     while !avro_schema.is_empty() {
         let mut found = false;
@@ -188,6 +228,13 @@ pub fn sort_messages_by_dependencies(avro_schema: &mut Vec<Value>) -> Vec<Value>
                 found = true;
                 continue;
             }
+            let own_name = full_name(record);
+            if own_name.as_ref().is_some_and(|n| defined.contains(n)) {
+                // Already defined inline in an earlier record
+                avro_schema.remove(i);
+                found = true;
+                continue;
+            }
             let deps: Vec<String> = record
                 .get("dependencies")
                 .and_then(|d| d.as_array())
@@ -199,19 +246,19 @@ pub fn sort_messages_by_dependencies(avro_schema: &mut Vec<Value>) -> Vec<Value>
                 .unwrap_or_default();
             let remaining_deps: Vec<String> = deps
                 .into_iter()
+                .filter(|d| Some(d) != own_name.as_ref())
                 .filter(|d| {
-                    !sorted_messages.iter().any(|s| {
-                        s.get("name").map(|n| n.as_str().unwrap()) == Some(d.as_str())
-                            || s.get("namespace").and_then(|n| n.as_str()).map(|ns| {
-                                format!("{}.{}", ns, s.get("name").unwrap().as_str().unwrap())
-                            }) == Some(d.clone())
-                    })
+                    !defined.contains(d)
+                        && !sorted_messages
+                            .iter()
+                            .any(|s| s.get("name").and_then(|n| n.as_str()) == Some(d.as_str()))
                 })
                 .collect();
 
             if remaining_deps.is_empty() {
                 let mut record_mut = avro_schema.remove(i);
                 record_mut.as_object_mut().unwrap().remove("dependencies");
+                collect_named_types(&record_mut, &mut defined);
                 sorted_messages.push(record_mut);
                 found = true;
                 continue;
@@ -226,10 +273,21 @@ pub fn sort_messages_by_dependencies(avro_schema: &mut Vec<Value>) -> Vec<Value>
                 .position(|r| r.get("dependencies").is_some())
             {
                 let mut record = avro_schema.remove(idx);
-                inline_dependencies_of(&mut sorted_messages.clone(), &mut record);
+                let mut candidates = avro_schema.clone();
+                candidates.extend(sorted_messages.iter().cloned());
+                inline_dependencies_of(&mut candidates, &mut record);
+                collect_named_types(&record, &mut defined);
                 sorted_messages.push(record);
             } else {
-                eprintln!("WARNING: Circular dependencies remain unresolved.");
+                let names: Vec<String> = avro_schema.iter().filter_map(full_name).collect();
+                diagnostics.warn(
+                    "unresolved-dependencies",
+                    None,
+                    format!(
+                        "dropped types with unresolved dependencies: {}",
+                        names.join(", ")
+                    ),
+                );
                 break;
             }
         }
@@ -239,6 +297,36 @@ pub fn sort_messages_by_dependencies(avro_schema: &mut Vec<Value>) -> Vec<Value>
     sorted_messages
 }
 
+/// Full name (`namespace.name`) of a named Avro type.
+fn full_name(avro_type: &Value) -> Option<String> {
+    let name = avro_type.get("name")?.as_str()?;
+    match avro_type.get("namespace").and_then(|n| n.as_str()) {
+        Some(ns) if !ns.is_empty() => Some(format!("{}.{}", ns, name)),
+        _ => Some(name.to_string()),
+    }
+}
+
+/// Collect the full names of all named types defined within `avro_type`.
+fn collect_named_types(avro_type: &Value, defined: &mut Vec<String>) {
+    match avro_type {
+        Value::Object(obj) => {
+            if matches!(
+                obj.get("type").and_then(|t| t.as_str()),
+                Some("record" | "enum" | "fixed")
+            ) {
+                defined.extend(full_name(avro_type));
+            }
+            for (key, value) in obj {
+                if key != "dependencies" {
+                    collect_named_types(value, defined);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_named_types(v, defined)),
+        _ => {}
+    }
+}
+
 /// Helper: swap dependency type inside a field.
 fn swap_dependency_type(
     _avro_schema: &mut Vec<Value>,
@@ -0,0 +1,73 @@
+//! Errors returned by the conversion entry points.
+use std::fmt;
+
+/// Why a JSON Schema could not be converted to Avro.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a local file failed.
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    /// A schema document could not be retrieved (network, offline mode,
+    /// unsupported URI scheme).
+    Fetch { uri: String, message: String },
+    /// A schema document is not valid JSON.
+    Json {
+        uri: String,
+        source: serde_json::Error,
+    },
+    /// A `$ref`, `$dynamicRef` or `$recursiveRef` has no target.
+    UnresolvableRef { reference: String, message: String },
+    /// A keyword is used in a way that cannot be expressed in Avro.
+    UnsupportedKeyword { keyword: String, message: String },
+    /// A name or namespace is not a valid Avro name.
+    InvalidName { name: String },
+    /// Two different named types share a full name.
+    NameCollision { name: String },
+    /// The generated schema is not well-formed Avro.
+    InvalidSchema { message: String },
+    /// A JSON instance does not fit the Avro schema; `path` is a JSON Pointer
+    /// into the instance.
+    InvalidData { path: String, message: String },
+    /// An Avro object container file is malformed.
+    InvalidContainer { message: String },
+}
+
+/// Result of a conversion step.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "I/O error on {path}: {source}"),
+            Error::Fetch { uri, message } => write!(f, "could not fetch {uri}: {message}"),
+            Error::Json { uri, source } => write!(f, "invalid JSON in {uri}: {source}"),
+            Error::UnresolvableRef { reference, message } => {
+                write!(f, "could not resolve {reference}: {message}")
+            }
+            Error::UnsupportedKeyword { keyword, message } => {
+                write!(f, "unsupported use of {keyword}: {message}")
+            }
+            Error::InvalidName { name } => write!(f, "invalid Avro name: {name:?}"),
+            Error::NameCollision { name } => {
+                write!(f, "different types share the Avro name {name}")
+            }
+            Error::InvalidSchema { message } => write!(f, "invalid Avro schema: {message}"),
+            Error::InvalidData { path, message } => write!(f, "invalid data at #{path}: {message}"),
+            Error::InvalidContainer { message } => {
+                write!(f, "invalid Avro object container file: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Json { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
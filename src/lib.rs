@@ -1,4 +1,4 @@
-#![cfg_attr(feature = "trace", allow(clippy::too_many_arguments))] // crustrace::omni expands fn signatures
+#![allow(clippy::too_many_arguments)] // converter state is threaded positionally; crustrace::omni expands fn signatures
 //! # avrotize
 //!
 //! Convert [JSON Schema](https://json-schema.org/) documents into
@@ -12,13 +12,15 @@
 //! - Generates records, enums, arrays, maps, and unions
 //! - Resolves and sorts type dependencies
 //! - CLI tool `jsonschema2avro` for batch conversion
+//! - Converts Avro schemas back to JSON Schema (`avro2jsonschema`)
 //!
 //! ## Example (Programmatic Usage)
 //!
 //! ```no_run
 //! use serde_json::json;
-//! use avrotize::converter::jsons_to_avro;
+//! use avrotize::converter::JsonToAvroConverter;
 //!
+//! # fn main() -> Result<(), avrotize::error::Error> {
 //! let schema = json!({
 //!     "$schema": "https://json-schema.org/draft/2020-12/schema",
 //!     "title": "Example",
@@ -30,15 +32,18 @@
 //!     "required": ["name"]
 //! });
 //!
-//! let avro = jsons_to_avro(
-//!     &schema,
-//!     "example_ns",          // namespace
-//!     "example_ns.utility",  // utility namespace
-//!     "example.json",        // base URI
-//!     false                  // don't split top-level
-//! );
+//! // A converter can be reused; external `$ref`s are fetched once
+//! let mut converter = JsonToAvroConverter::builder()
+//!     .namespace("example_ns")
+//!     .build();
+//! let avro = converter.convert_value(&schema, "example.json")?;
 //!
-//! println!("{}", serde_json::to_string_pretty(&avro).unwrap());
+//! for diagnostic in &avro.diagnostics {
+//!     eprintln!("{diagnostic}");
+//! }
+//! println!("{}", serde_json::to_string_pretty(&avro.schema).unwrap());
+//! # Ok(())
+//! # }
 //! ```
 //!
 //! ## Example (CLI)
@@ -53,15 +58,44 @@
 //! jsonschema2avro schema.json out_dir --split-top-level-records
 //! ```
 //!
+//! Or to check that a new schema version can read (and be read by) the old:
+//!
+//! ```bash
+//! jsonschema2avro check-compat old.avsc new.json
+//! ```
+//!
+//! Or to encode JSON instances, one per line, as an Avro object container
+//! file, and decode them back:
+//!
+//! ```bash
+//! jsonschema2avro encode schema.json data.jsonl out.avro --codec snappy
+//! jsonschema2avro decode out.avro data.jsonl
+//! ```
+//!
+//! Property names and enum values that had to be renamed for Avro are kept as
+//! `altnames` and `altsymbols` in the schema and restored by decoding, which
+//! can also be given the mapping written by converting with `--mapping`:
+//!
+//! ```bash
+//! jsonschema2avro schema.json out.avsc --mapping
+//! jsonschema2avro decode out.avro data.jsonl --mapping out.mapping.json
+//! ```
+//!
 //! ## Crate Layout
 //!
-//! - [`avro`] — Core Avro type definitions (`AvroType`, `AvroField`)
+//! - [`avro`] — Typed Avro schema model (`AvroType`, `AvroField`), (de)serializable as `.avsc` JSON, a spec [`avro::validate()`] check, [`avro::canonical`] fingerprints, [`avro::compat`] evolution checks and [`avro::idl`] output
+//! - [`avro2jsonschema`] — Avro → JSON Schema conversion, the inverse of [`converter`]
 //! - [`common`] — Helpers for names, hashing, traversal, etc.
-//! - [`converter`] — JSON Schema → Avro conversion logic
+//! - [`converter`] — JSON Schema → Avro conversion logic, with a [`converter::mapping`] back to the JSON Schema
+//! - [`data`] — Avro binary encoding and object container files of JSON instances, in the shape of the converted schema
 //! - [`dependency_resolver`] — Handles dependency ordering and inlining
+//! - [`error`] — The [`error::Error`] returned by the conversion entry points
 //!
 //! The CLI binary is enabled with the `cli` feature.
 pub mod avro;
+pub mod avro2jsonschema;
 pub mod common;
 pub mod converter;
+pub mod data;
 pub mod dependency_resolver;
+pub mod error;
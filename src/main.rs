@@ -1,18 +1,155 @@
 #[cfg(feature = "cli")]
+use avrotize::avro::{check_compatibility, AvroType, Compatibility};
+#[cfg(feature = "cli")]
+use avrotize::converter::diagnostics::{Diagnostic, Severity};
+#[cfg(feature = "cli")]
+use avrotize::converter::mapping::Mapping;
+#[cfg(feature = "cli")]
+use avrotize::converter::options::{ConverterOptions, EnumValues, TimePrecision};
+#[cfg(feature = "cli")]
+use avrotize::converter::references::Catalog;
+#[cfg(feature = "cli")]
+use avrotize::converter::{
+    write_avro, write_fingerprints, write_idl, write_mapping, JsonToAvroConverter,
+};
+#[cfg(feature = "cli")]
+use avrotize::data::ocf::DEFAULT_SYNC_INTERVAL;
+#[cfg(feature = "cli")]
+use avrotize::data::{Codec, Container, Encoder, Writer};
+#[cfg(feature = "cli")]
+use avrotize::error::Error;
+#[cfg(feature = "cli")]
 use clap::Parser;
 
+/// How diagnostics are printed to stderr.
+#[cfg(feature = "cli")]
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum DiagnosticsFormat {
+    /// One line per diagnostic
+    Text,
+    /// A JSON array of diagnostic objects
+    Json,
+}
+
 #[cfg(feature = "cli")]
 #[derive(Parser)]
-#[command(name = "jsonschema2avro", about = "Convert JSON Schema to Avro Schema")]
+#[command(
+    name = "jsonschema2avro",
+    about = "Convert JSON Schema to Avro Schema",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path or URL to the JSON Schema input
-    #[arg(value_name = "JSONSCHEMA")]
-    input: String,
+    #[arg(value_name = "JSONSCHEMA", required = true)]
+    input: Option<String>,
+
+    /// Path to the Avro schema output file; written as Avro IDL if it ends
+    /// in `.avdl`
+    #[arg(value_name = "AVRO", required = true)]
+    output: Option<String>,
 
-    /// Path to the Avro schema output file
-    #[arg(value_name = "AVRO")]
-    output: String,
+    /// Split top-level records into separate files
+    #[arg(long, default_value_t = false)]
+    split_top_level_records: bool,
 
+    /// Write CRC-64-AVRO, MD5 and SHA-256 fingerprints of each schema's
+    /// Parsing Canonical Form next to its `.avsc`
+    #[arg(long, default_value_t = false)]
+    fingerprint: bool,
+
+    /// Write where each named type came from in the JSON Schema, and the
+    /// original names of renamed properties and enum values, next to the
+    /// `.avsc`, for `decode --mapping`
+    #[arg(long, default_value_t = false)]
+    mapping: bool,
+
+    /// Wrap `.avdl` output in a protocol of this name
+    #[arg(long, value_name = "NAME")]
+    idl_protocol: Option<String>,
+
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+#[cfg(feature = "cli")]
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Check that a new schema can replace an old one under Avro schema
+    /// resolution, exiting with an error if it can't
+    CheckCompat {
+        /// The old schema: an Avro `.avsc` file, or a JSON Schema to convert
+        #[arg(value_name = "OLD")]
+        old: String,
+
+        /// The new schema: an Avro `.avsc` file, or a JSON Schema to convert
+        #[arg(value_name = "NEW")]
+        new: String,
+
+        /// Which compatibility to require
+        #[arg(long, value_enum, default_value_t = Compatibility::Full)]
+        level: Compatibility,
+
+        #[command(flatten)]
+        convert: ConvertArgs,
+    },
+    /// Encode JSON instances of a schema in the Avro binary encoding of its
+    /// converted schema
+    Encode {
+        /// The schema the instances are valid against: a JSON Schema to
+        /// convert, or the `.avsc` converted from it
+        #[arg(value_name = "SCHEMA")]
+        schema: String,
+
+        /// JSON lines file with one instance per line
+        #[arg(value_name = "DATA")]
+        data: String,
+
+        /// Path to write the Avro object container file to
+        #[arg(value_name = "OUT")]
+        output: String,
+
+        /// How to compress the file's blocks
+        #[arg(long, value_enum, default_value_t = Codec::Null)]
+        codec: Codec,
+
+        /// Uncompressed size in bytes at which a block is written out
+        #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_SYNC_INTERVAL)]
+        sync_interval: usize,
+
+        /// Write the encoded instances one after another, without a
+        /// container file's header and blocks
+        #[arg(long, default_value_t = false, conflicts_with_all = ["codec", "sync_interval"])]
+        raw: bool,
+
+        #[command(flatten)]
+        convert: ConvertArgs,
+    },
+    /// Decode an Avro object container file to JSON lines, one instance per
+    /// line in the shape of the JSON Schema it was converted from
+    Decode {
+        /// The Avro object container file
+        #[arg(value_name = "IN")]
+        input: String,
+
+        /// Path to write the JSON lines to
+        #[arg(value_name = "OUT")]
+        output: String,
+
+        /// The `.mapping.json` written by converting with `--mapping`, to
+        /// restore original names and unwrap exactly the wrapper records
+        #[arg(long, value_name = "FILE")]
+        mapping: Option<String>,
+    },
+}
+
+/// Options for converting JSON Schema input.
+#[cfg(feature = "cli")]
+#[derive(clap::Args)]
+struct ConvertArgs {
     /// Namespace override
     #[arg(long)]
     namespace: Option<String>,
@@ -25,14 +162,262 @@ struct Cli {
     #[arg(long)]
     root_class_name: Option<String>,
 
-    /// Split top-level records into separate files
+    /// Map a URI prefix to a local directory or file (PREFIX=PATH), repeatable
+    #[arg(long, value_name = "PREFIX=PATH")]
+    catalog: Vec<String>,
+
+    /// Never fetch remote URIs; they must be covered by --catalog
     #[arg(long, default_value_t = false)]
-    split_top_level_records: bool,
+    offline: bool,
+
+    /// Precision of timestamps and times from `date-time` and `time` formats
+    #[arg(long, value_enum, default_value_t = TimePrecision::Millis)]
+    time_precision: TimePrecision,
+
+    /// Map `date-time` to `local-timestamp-*` instead of `timestamp-*`
+    #[arg(long, default_value_t = false)]
+    local_timestamps: bool,
+
+    /// Decimal precision when a decimal's bounds don't determine it
+    #[arg(long, default_value_t = 38)]
+    decimal_precision: u32,
+
+    /// Decimal scale for `"x-avro-decimal": true` without a `multipleOf`
+    #[arg(long, default_value_t = 9)]
+    decimal_scale: u32,
+
+    /// Back decimals with `fixed` instead of `bytes`
+    #[arg(long, default_value_t = false)]
+    decimal_fixed: bool,
+
+    /// How to represent `enum` and `const` values that aren't all strings
+    #[arg(long, value_enum, default_value_t = EnumValues::Typed)]
+    enum_values: EnumValues,
+
+    /// Exit with an error if the conversion raised any warnings
+    #[arg(long, default_value_t = false)]
+    deny_warnings: bool,
+
+    /// How to print diagnostics
+    #[arg(long, value_enum, default_value_t = DiagnosticsFormat::Text)]
+    diagnostics_format: DiagnosticsFormat,
+}
+
+#[cfg(feature = "cli")]
+impl ConvertArgs {
+    /// A converter configured by these options.
+    fn converter(&self, split_top_level_records: bool) -> JsonToAvroConverter {
+        let mut catalog = Catalog::new();
+        for mapping in &self.catalog {
+            if let Err(e) = catalog.add_mapping(mapping) {
+                fail(e);
+            }
+        }
+
+        let options = ConverterOptions {
+            time_precision: self.time_precision,
+            local_timestamps: self.local_timestamps,
+            decimal_precision: self.decimal_precision,
+            decimal_scale: self.decimal_scale,
+            decimal_fixed: self.decimal_fixed,
+            enum_values: self.enum_values,
+        };
+        let mut builder = JsonToAvroConverter::builder()
+            .options(options)
+            .catalog(catalog)
+            .offline(self.offline)
+            .split_top_level_records(split_top_level_records);
+        if let Some(namespace) = &self.namespace {
+            builder = builder.namespace(namespace);
+        }
+        if let Some(utility_namespace) = &self.utility_namespace {
+            builder = builder.utility_namespace(utility_namespace);
+        }
+        if let Some(root_class_name) = &self.root_class_name {
+            builder = builder.root_class_name(root_class_name);
+        }
+        builder.build()
+    }
+
+    /// Print `diagnostics` to stderr, exiting if warnings are denied.
+    fn report(&self, diagnostics: &[Diagnostic]) {
+        match self.diagnostics_format {
+            DiagnosticsFormat::Text => {
+                for diagnostic in diagnostics {
+                    eprintln!("{diagnostic}");
+                }
+            }
+            DiagnosticsFormat::Json => {
+                eprintln!("{}", serde_json::to_string(&diagnostics).unwrap());
+            }
+        }
+
+        let warnings = diagnostics
+            .iter()
+            .filter(|d| d.severity >= Severity::Warning)
+            .count();
+        if self.deny_warnings && warnings > 0 {
+            fail(format!("{warnings} warning(s) denied by --deny-warnings"));
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+fn fail(error: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {error}");
+    std::process::exit(1);
+}
+
+/// Read an `.avsc` file, or convert any other input as JSON Schema.
+#[cfg(feature = "cli")]
+fn load_schema(
+    path: &str,
+    converter: &mut JsonToAvroConverter,
+) -> Result<(AvroType, Vec<Diagnostic>), Error> {
+    if !path.ends_with(".avsc") {
+        return converter
+            .convert_url(path)
+            .map(|conversion| (conversion.schema, conversion.diagnostics));
+    }
+    let content = std::fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    let schema = serde_json::from_str(&content).map_err(|source| Error::Json {
+        uri: path.to_string(),
+        source,
+    })?;
+    Ok((schema, Vec::new()))
+}
+
+#[cfg(feature = "cli")]
+fn check_compat(old: &str, new: &str, level: Compatibility, convert: &ConvertArgs) {
+    let mut converter = convert.converter(false);
+    let mut diagnostics = Vec::new();
+    let mut load = |path: &str| match load_schema(path, &mut converter) {
+        Ok((schema, found)) => {
+            diagnostics.extend(found);
+            schema
+        }
+        Err(e) => fail(e),
+    };
+    let old = load(old);
+    let new = load(new);
+    convert.report(&diagnostics);
+
+    let incompatibilities = check_compatibility(&old, &new, level);
+    if incompatibilities.is_empty() {
+        println!("{level} compatible");
+        return;
+    }
+    for incompatibility in &incompatibilities {
+        println!("{incompatibility}");
+    }
+    fail(format!(
+        "{} incompatibilit{} found for {level}",
+        incompatibilities.len(),
+        if incompatibilities.len() == 1 {
+            "y"
+        } else {
+            "ies"
+        }
+    ));
+}
+
+#[cfg(feature = "cli")]
+fn encode(
+    schema: &str,
+    data: &str,
+    output: &str,
+    codec: Codec,
+    sync_interval: usize,
+    raw: bool,
+    convert: &ConvertArgs,
+) {
+    let mut converter = convert.converter(false);
+    let (schema, diagnostics) = load_schema(schema, &mut converter).unwrap_or_else(|e| fail(e));
+    convert.report(&diagnostics);
+
+    let content = std::fs::read_to_string(data).unwrap_or_else(|source| {
+        fail(Error::Io {
+            path: data.to_string(),
+            source,
+        })
+    });
+    let encoder = Encoder::new(&schema);
+    let mut encoded = Vec::new();
+    let mut writer = Writer::new(&schema, codec).sync_interval(sync_interval);
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let location = format!("{data}:{}", number + 1);
+        let value: serde_json::Value = serde_json::from_str(line).unwrap_or_else(|source| {
+            fail(Error::Json {
+                uri: location.clone(),
+                source,
+            })
+        });
+        let result = if raw {
+            encoder.encode(&value, &mut encoded)
+        } else {
+            writer.append(&value)
+        };
+        if let Err(e) = result {
+            fail(format!("{location}: {e}"));
+        }
+    }
+    if !raw {
+        encoded = writer.finish();
+    }
+    if let Err(source) = std::fs::write(output, encoded) {
+        fail(Error::Io {
+            path: output.to_string(),
+            source,
+        });
+    }
+}
+
+#[cfg(feature = "cli")]
+fn decode(input: &str, output: &str, mapping: Option<&str>) {
+    let read = |path: &str| {
+        std::fs::read(path).unwrap_or_else(|source| {
+            fail(Error::Io {
+                path: path.to_string(),
+                source,
+            })
+        })
+    };
+    let mapping: Option<Mapping> = mapping.map(|path| {
+        serde_json::from_slice(&read(path)).unwrap_or_else(|source| {
+            fail(Error::Json {
+                uri: path.to_string(),
+                source,
+            })
+        })
+    });
+    let bytes = read(input);
+    let values = Container::read(&bytes)
+        .and_then(|container| match &mapping {
+            Some(mapping) => container.mapped_values(mapping),
+            None => container.values(),
+        })
+        .unwrap_or_else(|e| fail(format!("{input}: {e}")));
+    let lines: String = values.iter().map(|value| format!("{value}\n")).collect();
+    if let Err(source) = std::fs::write(output, lines) {
+        fail(Error::Io {
+            path: output.to_string(),
+            source,
+        });
+    }
 }
 
 /// Command-line entry point for `jsonschema2avro`.
 ///
-/// Converts a JSON Schema file into Avro schema `.avsc` files.
+/// Converts a JSON Schema file into Avro schema `.avsc` files, with
+/// `check-compat` checks two schemas for compatibility, with `encode`
+/// encodes JSON data as an Avro object container file, or with `decode`
+/// decodes one back to JSON.
 ///
 /// See `jsonschema2avro --help` for options.
 #[cfg(feature = "cli")]
@@ -62,16 +447,53 @@ fn main() {
 
     let cli = Cli::parse();
 
-    if let Err(e) = avrotize::converter::convert_jsons_to_avro(
-        &cli.input,
-        &cli.output,
-        cli.namespace.as_deref(),
-        cli.utility_namespace.as_deref(),
-        cli.root_class_name.as_deref(),
-        cli.split_top_level_records,
-    ) {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+    match &cli.command {
+        Some(Command::CheckCompat {
+            old,
+            new,
+            level,
+            convert,
+        }) => return check_compat(old, new, *level, convert),
+        Some(Command::Encode {
+            schema,
+            data,
+            output,
+            codec,
+            sync_interval,
+            raw,
+            convert,
+        }) => return encode(schema, data, output, *codec, *sync_interval, *raw, convert),
+        Some(Command::Decode {
+            input,
+            output,
+            mapping,
+        }) => return decode(input, output, mapping.as_deref()),
+        None => {}
+    }
+
+    let (Some(input), Some(output)) = (&cli.input, &cli.output) else {
+        unreachable!("clap requires the input and output without a subcommand")
+    };
+    let mut converter = cli.convert.converter(cli.split_top_level_records);
+    let conversion = converter.convert_url(input).unwrap_or_else(|e| fail(e));
+    // Denied warnings fail before any output is written
+    cli.convert.report(&conversion.diagnostics);
+    let written = if output.ends_with(".avdl") {
+        write_idl(&conversion.schema, output, cli.idl_protocol.as_deref())
+    } else {
+        write_avro(&conversion.schema, output, cli.split_top_level_records)
+    }
+    .and_then(|()| {
+        if cli.fingerprint {
+            write_fingerprints(&conversion.schema, output, cli.split_top_level_records)?;
+        }
+        if cli.mapping {
+            write_mapping(&conversion.mapping, output, cli.split_top_level_records)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = written {
+        fail(e);
     }
 }
 
@@ -0,0 +1,269 @@
+use avrotize::avro::{
+    canonical_form, check_compatibility, to_idl, validate, validate_documents, AvroType,
+    Compatibility, Fingerprints,
+};
+use rstest::rstest;
+use serde_json::{json, Value};
+
+fn check(schema: Value) -> Result<(), String> {
+    let schema = AvroType::try_from(schema).unwrap();
+    validate(&schema).map_err(|e| e.to_string())
+}
+
+/// Schemas breaking a rule of the Avro specification are rejected.
+#[rstest]
+#[case(json!({"type": "record", "name": "1st", "fields": []}), "invalid Avro name: \"1st\"")]
+#[case(json!({"type": "record", "name": "string", "fields": []}), "invalid Avro name: \"string\"")]
+#[case(
+    json!({"type": "record", "name": "R", "namespace": "a-b", "fields": []}),
+    "invalid Avro name: \"a-b\""
+)]
+#[case(
+    json!([
+        {"type": "fixed", "name": "Id", "namespace": "ns", "size": 4},
+        {"type": "enum", "name": "ns.Id", "symbols": ["A"]}
+    ]),
+    "different types share the Avro name ns.Id"
+)]
+#[case(
+    json!({"type": "record", "name": "R", "fields": [{"name": "next", "type": "Node"}]}),
+    "reference to undefined type Node in R.next"
+)]
+#[case(
+    json!({"type": "record", "name": "R", "fields": [
+        {"name": "a", "type": ["null", ["int", "string"]]}
+    ]}),
+    "union directly contains a union in R.a"
+)]
+#[case(
+    json!({"type": "map", "values": [
+        {"type": "array", "items": "int"},
+        {"type": "array", "items": "string"}
+    ]}),
+    "union contains two array types"
+)]
+#[case(
+    json!(["string", {"type": "string", "format": "email"}]),
+    "union contains two string types"
+)]
+#[case(
+    json!({"type": "record", "name": "R", "fields": [
+        {"name": "a", "type": ["null", "int"], "default": 1}
+    ]}),
+    "default 1 does not match the type in R.a"
+)]
+#[case(
+    json!({"type": "enum", "name": "E", "symbols": ["A", "B", "A"]}),
+    "duplicate symbol A in enum E"
+)]
+#[case(
+    json!({"type": "enum", "name": "E", "symbols": ["A", "B"], "default": "C"}),
+    "default C of enum E is not one of its symbols"
+)]
+#[case(json!({"type": "enum", "name": "E", "symbols": ["a-b"]}), "invalid Avro name: \"a-b\"")]
+fn invalid_schemas(#[case] schema: Value, #[case] message: &str) {
+    let error = check(schema).unwrap_err();
+    assert!(error.contains(message), "{error}");
+}
+
+/// Recursion, references by short name within a namespace, distinct named
+/// types in a union and defaults for the first branch are all valid.
+#[test]
+fn valid_schema() {
+    let schema = json!({
+        "type": "record",
+        "name": "Node",
+        "namespace": "tree",
+        "fields": [
+            {"name": "children", "type": {"type": "array", "items": "Node"}, "default": []},
+            {"name": "parent", "type": ["null", "tree.Node"], "default": null},
+            {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["LEAF", "BRANCH"]}},
+            {"name": "id", "type": [{"type": "fixed", "name": "Id", "size": 2}, "Kind", "string"], "default": "ab"},
+            {"name": "at", "type": [{"type": "int", "logicalType": "date"}, "long"], "default": 0}
+        ]
+    });
+    assert_eq!(check(schema), Ok(()));
+}
+
+/// Separately written schemas may refer to each other's types, even in a
+/// cycle, which a single schema can't do before defining them.
+#[test]
+fn documents_refer_to_each_other() {
+    let schemas = [
+        json!({"type": "record", "name": "a.Employee", "fields": [{"name": "dept", "type": "a.Department"}]}),
+        json!({"type": "record", "name": "a.Department", "fields": [{"name": "head", "type": "a.Employee"}]}),
+    ]
+    .map(|schema| AvroType::try_from(schema).unwrap());
+    assert!(validate(&AvroType::Union(schemas.to_vec())).is_err());
+    assert!(validate_documents(&schemas).is_ok());
+}
+
+/// Canonical form drops everything but the attributes that affect parsing,
+/// and qualifies names by their namespace.
+#[test]
+fn canonical_form_strips_and_qualifies() {
+    let schema = json!({
+        "type": "record",
+        "name": "Node",
+        "namespace": "tree",
+        "doc": "A tree",
+        "aliases": ["Leaf"],
+        "fields": [
+            {"name": "when", "type": {"type": "long", "logicalType": "timestamp-millis"}, "doc": "x"},
+            {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A"], "default": "A"}},
+            {"name": "tags", "type": {"type": "map", "values": "string", "format": "x"}, "default": {}},
+            {"name": "children", "type": {"type": "array", "items": "Node"}},
+            {"name": "id", "type": ["null", {"type": "fixed", "name": "other.Id", "size": 4}]}
+        ]
+    });
+    let schema = AvroType::try_from(schema).unwrap();
+    assert_eq!(
+        canonical_form(&schema),
+        concat!(
+            r#"{"name":"tree.Node","type":"record","fields":["#,
+            r#"{"name":"when","type":"long"},"#,
+            r#"{"name":"kind","type":{"name":"tree.Kind","type":"enum","symbols":["A"]}},"#,
+            r#"{"name":"tags","type":{"type":"map","values":"string"}},"#,
+            r#"{"name":"children","type":{"type":"array","items":"tree.Node"}},"#,
+            r#"{"name":"id","type":["null",{"name":"other.Id","type":"fixed","size":4}]}]}"#
+        )
+    );
+}
+
+/// Fingerprints agree with the reference values published with Avro.
+#[rstest]
+#[case(json!("null"), 7195948357588979594)]
+#[case(json!({"type": "int"}), 8247732601305521295)]
+fn crc64_fingerprints(#[case] schema: Value, #[case] crc64: u64) {
+    let schema = AvroType::try_from(schema).unwrap();
+    assert_eq!(Fingerprints::of(&schema).crc64, crc64);
+}
+
+#[test]
+fn fingerprints_serialize_as_hex() {
+    let fingerprints = Fingerprints::of(&AvroType::primitive("int"));
+    assert_eq!(
+        serde_json::to_value(&fingerprints).unwrap(),
+        json!({
+            "crc64-avro": "8f5c393f1ad57572",
+            "md5": "ef524ea1b91e73173d938ade36c1db32",
+            "sha256": "3f2b87a9fe7cc9b13835598c3981cd45e3e355309e5090aa0933d7becb6fba45"
+        })
+    );
+}
+
+fn breaks(old: Value, new: Value, level: Compatibility) -> Vec<String> {
+    let old = AvroType::try_from(old).unwrap();
+    let new = AvroType::try_from(new).unwrap();
+    check_compatibility(&old, &new, level)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Renaming a record breaks compatibility unless the new name lists the old
+/// one as an alias.
+#[test]
+fn compat_renamed_record() {
+    let old = json!({"type": "record", "name": "a.Old", "fields": []});
+    let renamed = json!({"type": "record", "name": "a.New", "fields": []});
+    let aliased = json!({"type": "record", "name": "a.New", "aliases": ["Old"], "fields": []});
+    assert_eq!(
+        breaks(old.clone(), renamed, Compatibility::Backward),
+        ["BACKWARD: a.New was renamed from a.Old without an alias at a.New"]
+    );
+    assert!(breaks(old, aliased, Compatibility::Backward).is_empty());
+}
+
+/// Numeric types may widen for readers, but not narrow.
+#[rstest]
+#[case("int", "long", Compatibility::Backward, true)]
+#[case("int", "long", Compatibility::Forward, false)]
+#[case("float", "double", Compatibility::Full, false)]
+#[case("string", "bytes", Compatibility::Full, true)]
+#[case("boolean", "int", Compatibility::Backward, false)]
+fn compat_promotion(
+    #[case] old: &str,
+    #[case] new: &str,
+    #[case] level: Compatibility,
+    #[case] compatible: bool,
+) {
+    let record =
+        |t: &str| json!({"type": "record", "name": "R", "fields": [{"name": "n", "type": t}]});
+    assert_eq!(
+        breaks(record(old), record(new), level).is_empty(),
+        compatible
+    );
+}
+
+/// Fields added with a default, removed enum symbols covered by a default
+/// and recursive records are all compatible.
+#[test]
+fn compat_defaults_and_recursion() {
+    let old = json!({"type": "record", "name": "Node", "fields": [
+        {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}},
+        {"name": "next", "type": ["null", "Node"]}
+    ]});
+    let new = json!({"type": "record", "name": "Node", "fields": [
+        {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "C"], "default": "A"}},
+        {"name": "next", "type": ["null", "Node"]},
+        {"name": "size", "type": {"type": "fixed", "name": "Size", "size": 4}, "default": "abcd"}
+    ]});
+    assert!(breaks(old.clone(), new.clone(), Compatibility::Backward).is_empty());
+    assert_eq!(
+        breaks(old, new, Compatibility::Forward),
+        ["FORWARD: writer symbol C is missing from the reader enum, which has no default at Node.kind"]
+    );
+}
+
+/// IDL renders docs as doc comments, aliases, defaults and custom attributes
+/// as annotations, and escapes identifiers that are keywords.
+#[test]
+fn idl_annotations() {
+    let schema = json!({
+        "type": "record",
+        "name": "Item",
+        "namespace": "shop",
+        "aliases": ["Product"],
+        "doc": "An item.\n\nSold by the shop.",
+        "fields": [
+            {"name": "record", "type": "string", "aliases": ["entry"], "doc": "Its record"},
+            {"name": "kind", "type": {
+                "type": "enum", "name": "Kind", "namespace": "shop.types",
+                "symbols": ["NEW", "USED"], "default": "NEW"
+            }, "default": "USED"},
+            {"name": "email", "type": ["null", {"type": "string", "format": "email"}], "default": null},
+            {"name": "seen", "type": {"type": "long", "logicalType": "timestamp-micros"}},
+            {"name": "tags", "type": {"type": "map", "values": {"type": "array", "items": "string"}}, "default": {}}
+        ]
+    });
+    let schema = AvroType::try_from(schema).unwrap();
+    assert_eq!(
+        to_idl(&schema, Some("Shop")),
+        r#"@namespace("shop")
+protocol Shop {
+
+  @namespace("shop.types")
+  enum Kind {
+    NEW,
+    USED
+  } = NEW;
+
+  /**
+   * An item.
+   *
+   * Sold by the shop.
+   */
+  @aliases(["Product"])
+  record Item {
+    /** Its record */
+    string @aliases(["entry"]) `record`;
+    shop.types.Kind kind = "USED";
+    union { null, @format("email") string } email = null;
+    @logicalType("timestamp-micros") long seen;
+    map<array<string>> tags = {};
+  }
+}
+"#
+    );
+}
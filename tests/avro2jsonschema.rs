@@ -0,0 +1,174 @@
+use avrotize::avro::{canonical_form, AvroType};
+use avrotize::avro2jsonschema::avro_to_jsons;
+use avrotize::converter::JsonToAvroConverter;
+use rstest::rstest;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Named types of a schema by full name, and by short name as a fallback.
+fn named_types(avro_type: &AvroType, namespace: &str, named: &mut HashMap<String, AvroType>) {
+    let ns = match avro_type.full_name() {
+        Some(full_name) if !full_name.contains('.') && !namespace.is_empty() => {
+            let full_name = format!("{namespace}.{full_name}");
+            named.insert(full_name, avro_type.clone());
+            namespace.to_string()
+        }
+        Some(full_name) => {
+            let ns = full_name
+                .rsplit_once('.')
+                .map_or("", |(ns, _)| ns)
+                .to_string();
+            let short = full_name.rsplit('.').next().unwrap().to_string();
+            named.insert(short, avro_type.clone());
+            named.insert(full_name, avro_type.clone());
+            ns
+        }
+        None => namespace.to_string(),
+    };
+    match avro_type {
+        AvroType::Record { fields, .. } => {
+            for field in fields {
+                named_types(&field.field_type, &ns, named);
+            }
+        }
+        AvroType::Array { items: inner, .. } | AvroType::Map { values: inner, .. } => {
+            named_types(inner, &ns, named)
+        }
+        AvroType::Union(types) => types.iter().for_each(|t| named_types(t, &ns, named)),
+        _ => {}
+    }
+}
+
+/// Compares the shape of two schemas, ignoring the names of named types.
+struct Shape<'a> {
+    left: &'a HashMap<String, AvroType>,
+    right: &'a HashMap<String, AvroType>,
+    /// Pairs of named types being compared, assumed equal on recursion.
+    visiting: HashSet<(String, String)>,
+}
+
+impl Shape<'_> {
+    fn resolve<'t>(names: &'t HashMap<String, AvroType>, t: &'t AvroType) -> &'t AvroType {
+        match t {
+            AvroType::Reference(name) => {
+                let short = name.rsplit('.').next().unwrap();
+                names.get(name).or_else(|| names.get(short)).unwrap_or(t)
+            }
+            AvroType::Union(types) if types.len() == 1 => Self::resolve(names, &types[0]),
+            _ => t,
+        }
+    }
+
+    fn same(&mut self, a: &AvroType, b: &AvroType) -> bool {
+        let (a, b) = (Self::resolve(self.left, a), Self::resolve(self.right, b));
+        if let (Some(x), Some(y)) = (a.full_name(), b.full_name()) {
+            if !self.visiting.insert((x, y)) {
+                return true;
+            }
+        }
+        match (a, b) {
+            (AvroType::Primitive { r#type: x, .. }, AvroType::Primitive { r#type: y, .. }) => {
+                x == y
+            }
+            (AvroType::Record { fields: x, .. }, AvroType::Record { fields: y, .. }) => {
+                x.len() == y.len()
+                    && x.iter()
+                        .zip(y)
+                        .all(|(f, g)| f.name == g.name && self.same(&f.field_type, &g.field_type))
+            }
+            (AvroType::Enum { symbols: x, .. }, AvroType::Enum { symbols: y, .. }) => x == y,
+            (AvroType::Fixed { size: x, .. }, AvroType::Fixed { size: y, .. }) => x == y,
+            (AvroType::Array { items: x, .. }, AvroType::Array { items: y, .. })
+            | (AvroType::Map { values: x, .. }, AvroType::Map { values: y, .. }) => self.same(x, y),
+            (
+                AvroType::Logical {
+                    logical_type: x,
+                    base: p,
+                    ..
+                },
+                AvroType::Logical {
+                    logical_type: y,
+                    base: q,
+                    ..
+                },
+            ) => x == y && self.same(p, q),
+            (AvroType::Union(x), AvroType::Union(y)) => {
+                x.len() == y.len() && x.iter().zip(y).all(|(s, t)| self.same(s, t))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The root of a schema, the last of a list of top-level types.
+fn root(schema: &AvroType) -> &AvroType {
+    match schema {
+        AvroType::Union(types) if !types.is_empty() => types.last().unwrap(),
+        other => other,
+    }
+}
+
+/// Fixtures that don't convert: remote refs without a catalog, and schemas
+/// that are errors by design.
+const EXPECTED_FAILURES: &[&str] = &[
+    "object_with_remote_ref",
+    "recursive_array",
+    "unresolvable_ref",
+];
+
+/// Converting a fixture to Avro, back to JSON Schema and to Avro again gives
+/// a schema of the same shape, though named types may be renamed.
+#[rstest]
+fn round_trip(#[files("tests/fixtures/jsonschema/*.json")] path: PathBuf) {
+    let mut converter = JsonToAvroConverter::builder()
+        .namespace("roundtrip")
+        .offline(true)
+        .build();
+    let stem = path.file_stem().unwrap().to_str().unwrap();
+    let converted = converter.convert_file(path.to_str().unwrap());
+    if EXPECTED_FAILURES.contains(&stem) {
+        assert!(converted.is_err(), "{stem} was expected not to convert");
+        return;
+    }
+    let avro = converted.unwrap_or_else(|e| panic!("{stem}: {e}"));
+    let json_schema = avro_to_jsons(&avro.schema);
+    let again = converter
+        .convert_value(&json_schema, path.to_str().unwrap())
+        .unwrap_or_else(|e| panic!("{e}\n{json_schema:#}"));
+
+    let (mut left, mut right) = (HashMap::new(), HashMap::new());
+    named_types(&avro.schema, "", &mut left);
+    named_types(&again.schema, "", &mut right);
+    let mut shape = Shape {
+        left: &left,
+        right: &right,
+        visiting: HashSet::new(),
+    };
+    assert!(
+        shape.same(root(&avro.schema), root(&again.schema)),
+        "{}\n{}\n{json_schema:#}",
+        canonical_form(&avro.schema),
+        canonical_form(&again.schema)
+    );
+}
+
+/// Property names and enum values renamed for Avro are restored from the
+/// `altnames` and `altsymbols` conversion keeps them in.
+#[test]
+fn original_names() {
+    let avro = JsonToAvroConverter::builder()
+        .build()
+        .convert_file("tests/fixtures/jsonschema/object_renamed_properties.json")
+        .unwrap();
+    let json_schema = avro_to_jsons(&avro.schema);
+    let properties = &json_schema["properties"];
+    assert_eq!(
+        properties.as_object().unwrap().keys().collect::<Vec<_>>(),
+        ["first-name", "2fa", "status", "home address"]
+    );
+    assert_eq!(json_schema["required"], json!(["first-name", "status"]));
+    let status = properties["status"]["$ref"].as_str().unwrap();
+    let status = &json_schema["$defs"][status.rsplit('/').next().unwrap()];
+    assert_eq!(status["enum"], json!(["in progress", "done", "1st"]));
+}
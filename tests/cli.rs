@@ -1,5 +1,6 @@
 #![cfg(feature = "cli")]
 use assert_cmd::Command;
+use avrotize::avro::{AvroType, Fingerprints};
 use insta::{assert_json_snapshot, assert_snapshot};
 use rstest::rstest;
 use serde_json::Value;
@@ -7,7 +8,23 @@ use similar::{ChangeTag, TextDiff};
 use std::{fs, path::Path};
 use tempfile::tempdir;
 
+/// Remote refs in fixtures resolve against bundled copies, never the network.
+fn catalog_args() -> [String; 3] {
+    [
+        "--offline".to_string(),
+        "--catalog".to_string(),
+        format!(
+            "https://json-schema.org/={}/tests/fixtures/catalog/json-schema.org",
+            env!("CARGO_MANIFEST_DIR")
+        ),
+    ]
+}
+
 fn run_fixture(schema_path: &str, stem: &str) {
+    run_fixture_with_args(schema_path, stem, stem, &[]);
+}
+
+fn run_fixture_with_args(schema_path: &str, stem: &str, snapshot: &str, args: &[&str]) {
     let dir = tempdir().unwrap();
     let input_path = dir.path().join(format!("{stem}.json"));
     let output_path = dir.path().join(format!("{stem}.avsc"));
@@ -21,6 +38,8 @@ fn run_fixture(schema_path: &str, stem: &str) {
         .unwrap()
         .arg(input_path.to_str().unwrap())
         .arg(output_path.to_str().unwrap())
+        .args(catalog_args())
+        .args(args)
         .assert()
         .success();
 
@@ -29,7 +48,7 @@ fn run_fixture(schema_path: &str, stem: &str) {
     let json: serde_json::Value = serde_json::from_str(&output).unwrap();
 
     // Compare with snapshot
-    assert_json_snapshot!(stem, json);
+    assert_json_snapshot!(snapshot, json);
 }
 
 #[rstest]
@@ -42,9 +61,14 @@ fn run_fixture(schema_path: &str, stem: &str) {
 #[case("basic_string_schema_with_title")]
 #[case("boolean_false_schema")]
 #[case("boolean_true_schema")]
+#[case("dynamic_ref_tree")]
 #[case("empty_schema")]
+#[case("enum_non_string")]
 #[case("enum_string_property")]
+#[case("integer_width")]
+#[case("mutually_recursive_defs")]
 #[case("nested_object_and_array")]
+#[case("number_decimal")]
 #[case("number_exclusive_max")]
 #[case("number_exclusive_min")]
 #[case("number_exclusive_multipleof")]
@@ -54,6 +78,9 @@ fn run_fixture(schema_path: &str, stem: &str) {
 #[case("object_dependentschemas")]
 #[case("object_if_then_else")]
 #[case("object_not")]
+#[case("object_renamed_properties")]
+#[case("object_required_nullable")]
+#[case("object_with_anchor_ref")]
 #[case("object_with_boolean_and_number")]
 #[case("object_with_const_field")]
 #[case("object_with_default_value")]
@@ -65,18 +92,215 @@ fn run_fixture(schema_path: &str, stem: &str) {
 #[case("object_with_oneof")]
 #[case("object_with_optional")]
 #[case("object_with_remote_ref")]
+#[case("string_binary_content")]
+#[case("string_format_annotations")]
 #[case("string_format_email")]
+#[case("string_format_temporal")]
 #[case("string_maxlength")]
 #[case("string_minlength")]
 // #[case("object_maxproperties")] // not priority?
 // #[case("object_minproperties")] // not priority?
 #[case("object_pattern_properties")]
-// #[case("recursive_ref")] // accesses ref at URL
+#[case("recursive_anchor_tree")]
+#[case("recursive_ref")]
 fn cli_fixtures(#[case] stem: &str) {
     let schema_path = format!("tests/fixtures/jsonschema/{stem}.json");
     run_fixture(&schema_path, stem);
 }
 
+/// Fixtures converted with non-default converter options.
+#[rstest]
+#[case("string_format_temporal", "string_format_temporal_micros", &["--time-precision", "micros"])]
+#[case("string_format_temporal", "string_format_temporal_local", &["--local-timestamps"])]
+#[case("number_decimal", "number_decimal_fixed", &["--decimal-fixed", "--decimal-precision", "20"])]
+#[case("recursive_ref", "recursive_ref_root_class_name", &["--root-class-name", "Tree"])]
+fn cli_fixtures_with_options(#[case] stem: &str, #[case] snapshot: &str, #[case] args: &[&str]) {
+    let schema_path = format!("tests/fixtures/jsonschema/{stem}.json");
+    run_fixture_with_args(&schema_path, stem, snapshot, args);
+}
+
+/// Fixtures with relative `$ref`s to sibling files are converted in place,
+/// so that the references resolve against the fixtures directory.
+#[rstest]
+#[case("object_with_external_ref")]
+fn cli_fixtures_in_place(#[case] stem: &str) {
+    let dir = tempdir().unwrap();
+    let schema_path = format!("tests/fixtures/jsonschema/{stem}.json");
+    let output_path = dir.path().join(format!("{stem}.avsc"));
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg(&schema_path)
+        .arg(output_path.to_str().unwrap())
+        .args(catalog_args())
+        .assert()
+        .success();
+
+    let output = fs::read_to_string(&output_path).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_json_snapshot!(stem, json);
+}
+
+/// Schemas that cannot be converted fail with an error naming the cause.
+#[rstest]
+#[case("unresolvable_ref", &[], "could not resolve #/$defs/Person")]
+#[case("recursive_array", &[], "unsupported use of $ref")]
+#[case("basic_string_schema", &["--namespace", "my-ns"], "invalid Avro name: \"my-ns\"")]
+fn cli_errors(#[case] stem: &str, #[case] args: &[&str], #[case] message: &str) {
+    let dir = tempdir().unwrap();
+    let schema_path = format!("tests/fixtures/jsonschema/{stem}.json");
+    let output_path = dir.path().join(format!("{stem}.avsc"));
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg(&schema_path)
+        .arg(output_path.to_str().unwrap())
+        .args(catalog_args())
+        .args(args)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(message));
+
+    assert!(!output_path.exists());
+}
+
+/// Degraded conversions are reported as diagnostics pointing at the source keyword.
+#[rstest]
+#[case("integer_width")]
+#[case("string_binary_content")]
+fn cli_diagnostics(#[case] stem: &str) {
+    let dir = tempdir().unwrap();
+    let schema_path = format!("tests/fixtures/jsonschema/{stem}.json");
+    let output_path = dir.path().join(format!("{stem}.avsc"));
+
+    let output = Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg(&schema_path)
+        .arg(output_path.to_str().unwrap())
+        .args(["--diagnostics-format", "json"])
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let mut diagnostics: Value = serde_json::from_slice(&output).unwrap();
+    for diagnostic in diagnostics.as_array_mut().unwrap() {
+        diagnostic.as_object_mut().unwrap().remove("document");
+    }
+    assert_json_snapshot!(format!("{stem}.diagnostics"), diagnostics);
+
+    // Denied warnings leave no output behind
+    let denied_dir = tempdir().unwrap();
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg(&schema_path)
+        .arg(denied_dir.path().join(format!("{stem}.avsc")))
+        .args(["--deny-warnings", "--mapping", "--fingerprint"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("denied by --deny-warnings"));
+    assert_eq!(fs::read_dir(denied_dir.path()).unwrap().count(), 0);
+}
+
+/// An output path ending in `.avdl` is written as Avro IDL.
+#[rstest]
+#[case("object_with_defs", "object_with_defs", &[])]
+#[case("mutually_recursive_defs", "mutually_recursive_defs", &[])]
+#[case("string_format_temporal", "string_format_temporal", &[])]
+#[case("enum_string_property", "enum_string_property", &[])]
+#[case("object_with_default_value", "object_with_default_value", &[])]
+#[case("object_renamed_properties", "object_renamed_properties", &[])]
+#[case("enum_non_string", "enum_non_string_symbols", &["--enum-values", "symbols"])]
+#[case("number_decimal", "number_decimal_protocol", &["--idl-protocol", "Invoices", "--decimal-fixed"])]
+fn cli_idl(#[case] stem: &str, #[case] snapshot: &str, #[case] args: &[&str]) {
+    let dir = tempdir().unwrap();
+    let output_path = dir.path().join(format!("{stem}.avdl"));
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg(format!("tests/fixtures/jsonschema/{stem}.json"))
+        .arg(output_path.to_str().unwrap())
+        .args(args)
+        .assert()
+        .success();
+
+    assert_snapshot!(
+        format!("{snapshot}.avdl"),
+        fs::read_to_string(&output_path).unwrap()
+    );
+}
+
+/// With `--fingerprint`, each split `.avsc` gets the fingerprints of its
+/// canonical form alongside it.
+#[test]
+fn cli_fingerprint_split() {
+    let dir = tempdir().unwrap();
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg("tests/fixtures/jsonschema/mutually_recursive_defs.json")
+        .arg(dir.path().to_str().unwrap())
+        .args(["--split-top-level-records", "--fingerprint"])
+        .assert()
+        .success();
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        let Some(name) = path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .strip_suffix(".avsc")
+        else {
+            continue;
+        };
+        let schema: AvroType = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let written: Value = serde_json::from_str(
+            &fs::read_to_string(dir.path().join(format!("{name}.fingerprints.json"))).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            written,
+            serde_json::to_value(Fingerprints::of(&schema)).unwrap()
+        );
+        names.push(name.to_string());
+    }
+    names.sort();
+    assert_eq!(
+        names,
+        ["Department", "Employee", "ListNode", "Organisation"]
+    );
+}
+
+/// `check-compat` reports each break of the requested level, failing if any.
+#[rstest]
+#[case("person_v1.avsc", "person_v2.json", "full", false)]
+#[case("person_v1.avsc", "person_v2.json", "backward", false)]
+#[case("person_v1.avsc", "person_v2.json", "forward", false)]
+#[case("person_v1.avsc", "person_v1.avsc", "full", true)]
+fn cli_check_compat(
+    #[case] old: &str,
+    #[case] new: &str,
+    #[case] level: &str,
+    #[case] compatible: bool,
+) {
+    let assert = Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg("check-compat")
+        .arg(format!("tests/fixtures/compat/{old}"))
+        .arg(format!("tests/fixtures/compat/{new}"))
+        .args(["--namespace", "compat", "--level", level])
+        .assert();
+    let assert = if compatible {
+        assert.success()
+    } else {
+        assert.failure()
+    };
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert_snapshot!(format!("check_compat_{level}_{}", compatible), stdout);
+}
+
 fn normalize_json(input: &str) -> String {
     let value: Value = serde_json::from_str(input).unwrap();
     serde_json::to_string_pretty(&value).unwrap()
@@ -172,3 +396,148 @@ fn diff_snapshots(#[case] stem: &str) {
         assert_snapshot!(format!("{stem}.diff"), diff);
     }
 }
+
+/// `avro2jsonschema` converts an Avro schema back to JSON Schema.
+#[test]
+fn cli_avro2jsonschema() {
+    let dir = tempdir().unwrap();
+    let output_path = dir.path().join("order.json");
+
+    Command::cargo_bin("avro2jsonschema")
+        .unwrap()
+        .arg("tests/fixtures/avro/order.avsc")
+        .arg(output_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    let output: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+    assert_json_snapshot!("avro2jsonschema_order", output);
+}
+
+/// `encode --raw` writes each JSON line in the Avro binary encoding of the
+/// converted schema.
+#[test]
+fn cli_encode() {
+    let dir = tempdir().unwrap();
+    let output_path = dir.path().join("events.bin");
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .args([
+            "encode",
+            "--raw",
+            "tests/fixtures/jsonschema/string_format_temporal.json",
+            "tests/fixtures/data/string_format_temporal.jsonl",
+        ])
+        .arg(output_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    let encoded: Vec<String> = fs::read(&output_path)
+        .unwrap()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    assert_snapshot!("encode_string_format_temporal", encoded.join(" "));
+}
+
+/// An instance that doesn't fit the schema is reported with its line.
+#[test]
+fn cli_encode_invalid() {
+    let dir = tempdir().unwrap();
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .args([
+            "encode",
+            "tests/fixtures/jsonschema/string_format_temporal.json",
+            "tests/fixtures/data/string_format_temporal_invalid.jsonl",
+        ])
+        .arg(dir.path().join("events.bin").to_str().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "string_format_temporal_invalid.jsonl:2: invalid data at #/occurredAt: invalid date-time \"yesterday\"",
+        ));
+}
+
+/// With the `--mapping` written by conversion, `decode` restores the property
+/// names and enum values that aren't valid Avro names, and keeps the `null`s
+/// of required properties.
+#[rstest]
+#[case("object_renamed_properties")]
+#[case("object_required_nullable")]
+fn cli_decode_mapping(#[case] stem: &str) {
+    let dir = tempdir().unwrap();
+    let avsc_path = dir.path().join("profile.avsc");
+    let avro_path = dir.path().join("profile.avro");
+    let jsonl_path = dir.path().join("profile.jsonl");
+    let data = format!("tests/fixtures/data/{stem}.jsonl");
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg(format!("tests/fixtures/jsonschema/{stem}.json"))
+        .arg(avsc_path.to_str().unwrap())
+        .arg("--mapping")
+        .assert()
+        .success();
+    let mapping_path = dir.path().join("profile.mapping.json");
+    assert_snapshot!(
+        format!("{stem}.mapping"),
+        fs::read_to_string(&mapping_path).unwrap()
+    );
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .args(["encode", avsc_path.to_str().unwrap(), &data])
+        .arg(avro_path.to_str().unwrap())
+        .assert()
+        .success();
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg("decode")
+        .arg(avro_path.to_str().unwrap())
+        .arg(jsonl_path.to_str().unwrap())
+        .args(["--mapping", mapping_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(jsonl_path).unwrap(),
+        fs::read_to_string(&data).unwrap()
+    );
+}
+
+/// `encode` writes a container file that `decode` reads back to the same
+/// JSON lines, whatever the codec.
+#[rstest]
+fn cli_encode_decode(#[values("null", "deflate", "snappy")] codec: &str) {
+    let dir = tempdir().unwrap();
+    let avro_path = dir.path().join("events.avro");
+    let jsonl_path = dir.path().join("events.jsonl");
+    let data = "tests/fixtures/data/string_format_temporal_utc.jsonl";
+
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .args([
+            "encode",
+            "tests/fixtures/jsonschema/string_format_temporal.json",
+            data,
+        ])
+        .arg(avro_path.to_str().unwrap())
+        .args(["--codec", codec, "--sync-interval", "32"])
+        .assert()
+        .success();
+    Command::cargo_bin("jsonschema2avro")
+        .unwrap()
+        .arg("decode")
+        .arg(avro_path.to_str().unwrap())
+        .arg(jsonl_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read_to_string(jsonl_path).unwrap(),
+        fs::read_to_string(data).unwrap()
+    );
+}
@@ -0,0 +1,86 @@
+use avrotize::converter::references::base_url;
+use avrotize::converter::JsonToAvroConverter;
+use serde_json::json;
+
+const EXTERNAL_REF: &str = "tests/fixtures/jsonschema/object_with_external_ref.json";
+
+/// A converter reused across schemas gives each the same output it would get
+/// alone, while documents loaded for one are cached for the next.
+#[test]
+fn converter_is_reusable() {
+    let mut converter = JsonToAvroConverter::builder()
+        .namespace("reuse")
+        .offline(true)
+        .build();
+
+    let first = converter.convert_file(EXTERNAL_REF).unwrap();
+    let address = base_url(EXTERNAL_REF).join("common/address.json").unwrap();
+    assert!(converter.content_cache.get(address.as_str()).is_some());
+
+    let other = json!({
+        "type": "object",
+        "properties": { "id": { "type": "integer" } }
+    });
+    let other = converter.convert_value(&other, "other.json").unwrap();
+    assert_eq!(other.schema.full_name().as_deref(), Some("reuse.other"));
+
+    let again = converter.convert_file(EXTERNAL_REF).unwrap();
+    assert_eq!(first, again);
+    assert_eq!(
+        first,
+        JsonToAvroConverter::builder()
+            .namespace("reuse")
+            .build()
+            .convert_file(EXTERNAL_REF)
+            .unwrap()
+    );
+}
+
+/// The mapping leads from each named type and field to the subschema it was
+/// converted from, and back, keeping the names conversion changed.
+#[test]
+fn conversion_mapping() {
+    let conversion = JsonToAvroConverter::builder()
+        .build()
+        .convert_file("tests/fixtures/jsonschema/mutually_recursive_defs.json")
+        .unwrap();
+    let mapping = &conversion.mapping;
+    assert_eq!(
+        mapping.pointer("mutually_recursive_defs.Organisation"),
+        Some("#")
+    );
+    assert_eq!(
+        mapping.pointer("mutually_recursive_defs.Employee.manager"),
+        Some("#/$defs/Employee/properties/manager")
+    );
+    assert_eq!(
+        mapping.avro_path("#/$defs/Department").as_deref(),
+        Some("mutually_recursive_defs.Department")
+    );
+    assert_eq!(
+        mapping
+            .avro_path("#/$defs/Department/properties/head")
+            .as_deref(),
+        Some("mutually_recursive_defs.Department.head")
+    );
+
+    let renamed = JsonToAvroConverter::builder()
+        .build()
+        .convert_file("tests/fixtures/jsonschema/object_renamed_properties.json")
+        .unwrap()
+        .mapping;
+    let record = "object_renamed_properties.UserProfile";
+    assert_eq!(renamed.field(record, "_2fa").unwrap().name, "2fa");
+    assert_eq!(renamed.field(record, "status").unwrap().name, "status");
+    assert_eq!(
+        renamed.symbol(
+            "object_renamed_properties.UserProfile_types.status",
+            "in_progress"
+        ),
+        Some(&json!("in progress"))
+    );
+    assert_eq!(
+        renamed.symbol("object_renamed_properties.UserProfile_types.status", "done"),
+        None
+    );
+}
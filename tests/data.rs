@@ -0,0 +1,550 @@
+use avrotize::avro::validate::validate;
+use avrotize::avro::AvroType;
+use avrotize::converter::options::{ConverterOptions, EnumValues};
+use avrotize::converter::JsonToAvroConverter;
+use avrotize::data::codec::{crc32, deflate, snappy};
+use avrotize::data::{Codec, Container, Decoder, Encoder, Writer};
+use rstest::rstest;
+use serde_json::{json, Value};
+
+fn encode(schema: &AvroType, value: Value) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    Encoder::new(schema)
+        .encode(&value, &mut out)
+        .map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+fn encode_as(schema: Value, value: Value) -> Result<Vec<u8>, String> {
+    encode(&AvroType::try_from(schema).unwrap(), value)
+}
+
+fn fixture(stem: &str) -> AvroType {
+    JsonToAvroConverter::builder()
+        .build()
+        .convert_file(format!("tests/fixtures/jsonschema/{stem}.json"))
+        .unwrap()
+        .schema
+}
+
+/// Values are written as the Avro specification lays them out.
+#[rstest]
+#[case(json!("int"), json!(1), &[0x02])]
+#[case(json!("int"), json!(-1), &[0x01])]
+#[case(json!("long"), json!(-65), &[0x81, 0x01])]
+#[case(json!("boolean"), json!(true), &[0x01])]
+#[case(json!("string"), json!("foo"), &[0x06, b'f', b'o', b'o'])]
+#[case(json!("double"), json!(1), &[0, 0, 0, 0, 0, 0, 0xf0, 0x3f])]
+#[case(json!("bytes"), json!("AQI="), &[0x04, 0x01, 0x02])]
+#[case(json!({"type": "fixed", "name": "F", "size": 2}), json!("ab"), b"ab")]
+#[case(json!({"type": "array", "items": "int"}), json!([1, 2]), &[0x04, 0x02, 0x04, 0x00])]
+#[case(json!({"type": "array", "items": "int"}), json!([]), &[0x00])]
+#[case(json!({"type": "map", "values": "int"}), json!({"a": 1}), &[0x02, 0x02, b'a', 0x02, 0x00])]
+#[case(
+    json!({"type": "enum", "name": "E", "symbols": ["todo", "in_progress"]}),
+    json!("in-progress"),
+    &[0x02]
+)]
+fn encode_values(#[case] schema: Value, #[case] value: Value, #[case] expected: &[u8]) {
+    assert_eq!(encode_as(schema, value).unwrap(), expected);
+}
+
+/// Logical types are read from the JSON Schema formats they were converted from.
+#[rstest]
+#[case(json!({"type": "int", "logicalType": "date"}), json!("1969-12-31"), &[0x01])]
+#[case(
+    json!({"type": "long", "logicalType": "timestamp-millis"}),
+    json!("1970-01-01T01:00:01.500+01:00"),
+    &[0xb8, 0x17]
+)]
+#[case(
+    json!({"type": "long", "logicalType": "local-timestamp-millis"}),
+    json!("1970-01-01T00:00:01.5+01:00"),
+    &[0xb8, 0x17]
+)]
+#[case(json!({"type": "int", "logicalType": "time-millis"}), json!("00:00:01.5Z"), &[0xb8, 0x17])]
+#[case(
+    json!({"type": "fixed", "name": "D", "size": 12, "logicalType": "duration"}),
+    json!("P1M2DT3S"),
+    &[1, 0, 0, 0, 2, 0, 0, 0, 0xb8, 0x0b, 0, 0]
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!(12.34),
+    &[0x04, 0x04, 0xd2]
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!(-1),
+    &[0x02, 0x9c]
+)]
+#[case(
+    json!({"type": "fixed", "name": "Amount", "size": 4, "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!(-1),
+    &[0xff, 0xff, 0xff, 0x9c]
+)]
+fn encode_logical_types(#[case] schema: Value, #[case] value: Value, #[case] expected: &[u8]) {
+    assert_eq!(encode_as(schema, value).unwrap(), expected);
+}
+
+/// A union takes the first branch of the value's own kind before one it can
+/// only be coerced to.
+#[rstest]
+#[case(json!(null), &[0x00])]
+#[case(json!(3), &[0x04, 0x06])]
+#[case(json!(1_099_511_627_776_i64), &[0x06, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40])]
+#[case(json!(3.5), &[0x08, 0x00, 0x00, 0x60, 0x40])]
+#[case(json!("abc"), &[0x0e, 0x06, b'a', b'b', b'c'])]
+#[case(json!(["x"]), &[0x10, 0x02, 0x0e, 0x02, b'x', 0x00])]
+fn encode_generic_union(#[case] value: Value, #[case] expected: &[u8]) {
+    let schema = json!({"type": "record", "name": "R", "fields": [
+        {"name": "any", "type": avrotize::common::generic_type()}
+    ]});
+    assert_eq!(encode_as(schema, json!({"any": value})).unwrap(), expected);
+}
+
+/// Instances are written through the records the converter wraps roots,
+/// unions and pattern properties in.
+#[rstest]
+#[case("array_maxitems", json!([1, 2]), &[0x04, 0x02, 0x04, 0x00])]
+#[case("object_with_oneof", json!({"value": 5}), &[0x02, 0x0a])]
+#[case(
+    "object_pattern_properties",
+    json!({"S_a": "x", "I_b": 3}),
+    &[0x04, 0x06, b'S', b'_', b'a', 0x00, 0x02, b'x', 0x06, b'I', b'_', b'b', 0x02, 0x06, 0x00]
+)]
+#[case("object_with_enum_array", json!({"statuses": ["DONE", "NEW"]}), &[0x04, 0x04, 0x00, 0x00])]
+fn encode_converted(#[case] stem: &str, #[case] value: Value, #[case] expected: &[u8]) {
+    assert_eq!(encode(&fixture(stem), value).unwrap(), expected);
+}
+
+/// Absent optional properties are written as null, or as the field default.
+#[test]
+fn encode_missing_properties() {
+    let schema = json!({"type": "record", "name": "R", "fields": [
+        {"name": "a", "type": ["null", "int"]},
+        {"name": "b", "type": ["string", "null"], "default": "x"},
+        {"name": "c", "type": "int"}
+    ]});
+    assert_eq!(
+        encode_as(schema.clone(), json!({"c": 1})).unwrap(),
+        [0x00, 0x00, 0x02, b'x', 0x02]
+    );
+    assert_eq!(
+        encode_as(schema, json!({"a": 1})).unwrap_err(),
+        "invalid data at #: missing property \"c\""
+    );
+}
+
+/// A required property whose type list names `null` still takes `null`.
+#[test]
+fn encode_required_nullable() {
+    let conversion = JsonToAvroConverter::builder()
+        .build()
+        .convert_file("tests/fixtures/jsonschema/object_required_nullable.json")
+        .unwrap();
+    for value in [
+        json!({"name": null, "amount": null, "code": 1}),
+        json!({"name": "a", "amount": 2, "code": "x"}),
+    ] {
+        let bytes = encode(&conversion.schema, value.clone()).unwrap();
+        let decoded = Decoder::new(&conversion.schema)
+            .mapping(&conversion.mapping)
+            .decode(&mut bytes.as_slice())
+            .unwrap();
+        assert_eq!(decoded, value);
+    }
+}
+
+#[rstest]
+#[case(json!({"type": "enum", "name": "E", "symbols": ["A"]}), json!("B"), "at #: \"B\" is not a symbol of enum E")]
+#[case(json!("int"), json!(1_i64 << 40), "at #: an integer does not fit int")]
+#[case(json!({"type": "int", "enum": [1, 2]}), json!(3), "at #: 3 is not one of [1,2]")]
+#[case(
+    json!({"type": "enum", "name": "E", "symbols": ["_1"], "altsymbols": {"json": {"_1": 1}}}),
+    json!(2),
+    "at #: 2 is not a symbol of enum E"
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!(1.005),
+    "at #: 1.005 has more than 2 decimal places"
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 3, "scale": 2}),
+    json!(12.5),
+    "at #: 12.5 does not fit decimal(3, 2)"
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!("1e99999999999999"),
+    "at #: 1e99999999999999 does not fit decimal(5, 2)"
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!("1e9223372036854775807"),
+    "at #: 1e9223372036854775807 does not fit decimal(5, 2)"
+)]
+#[case(
+    json!({"type": "array", "items": ["null", "int"]}),
+    json!([1, "a"]),
+    "at #/1: a string fits no branch of the union"
+)]
+#[case(json!({"type": "int", "logicalType": "date"}), json!("1970-13-01"), "invalid date \"1970-13-01\"")]
+fn encode_errors(#[case] schema: Value, #[case] value: Value, #[case] message: &str) {
+    let error = encode_as(schema, value).unwrap_err();
+    assert!(error.contains(message), "{error}");
+}
+
+fn decode(schema: &AvroType, bytes: &[u8]) -> Value {
+    let mut input = bytes;
+    let value = Decoder::new(schema).decode(&mut input).unwrap();
+    assert!(input.is_empty(), "{} bytes left", input.len());
+    value
+}
+
+/// Decoding gives back the instance, in the JSON Schema formats of logical
+/// types.
+#[rstest]
+#[case(json!("float"), json!(0.1))]
+#[case(json!("double"), json!(2.5))]
+#[case(json!({"type": "fixed", "name": "F", "size": 2}), json!("AQI="))]
+#[case(json!({"type": "int", "logicalType": "date"}), json!("1969-12-31"))]
+#[case(json!({"type": "long", "logicalType": "timestamp-millis"}), json!("2024-03-01T12:30:00.25Z"))]
+#[case(json!({"type": "long", "logicalType": "timestamp-micros"}), json!("1900-01-01T00:00:00Z"))]
+#[case(json!({"type": "long", "logicalType": "local-timestamp-millis"}), json!("2000-02-29T23:59:59.999"))]
+#[case(json!({"type": "int", "logicalType": "time-millis"}), json!("00:00:01.5Z"))]
+#[case(
+    json!({"type": {"type": "fixed", "name": "D", "size": 12}, "logicalType": "duration"}),
+    json!("P1Y2M3DT1H30M0.5S")
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 5, "scale": 2}),
+    json!(-1.05)
+)]
+#[case(
+    json!({"type": "bytes", "logicalType": "decimal", "precision": 30, "scale": 2}),
+    json!("1234567890123456789.12")
+)]
+#[case(json!({"type": "string", "logicalType": "uuid"}), json!("00000000-0000-0000-0000-000000000000"))]
+#[case(json!({"type": "enum", "name": "E", "symbols": ["_1", "true"], "altsymbols": {"json": {"_1": 1, "true": true}}}), json!(true))]
+#[case(json!({"type": "double", "enum": [0.5, 1]}), json!(1))]
+fn decode_values(#[case] schema: Value, #[case] value: Value) {
+    let schema = AvroType::try_from(schema).unwrap();
+    let encoded = encode(&schema, value.clone()).unwrap();
+    assert_eq!(decode(&schema, &encoded), value);
+}
+
+/// A decimal that a JSON number holds exactly comes back as one, and one too
+/// wide for 128 bits is an error.
+#[test]
+fn decode_decimal_exact() {
+    let schema = AvroType::try_from(
+        json!({"type": "bytes", "logicalType": "decimal", "precision": 30, "scale": 2}),
+    )
+    .unwrap();
+    assert_eq!(decode(&schema, &[0x04, 0x04, 0xb0]), json!(12));
+    let mut wide: &[u8] = &[0x22, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    assert_eq!(
+        Decoder::new(&schema)
+            .decode(&mut wide)
+            .unwrap_err()
+            .to_string(),
+        "invalid data at #: decimal does not fit 128 bits"
+    );
+}
+
+/// The converter's wrapper records are undone, absent optional properties
+/// stay absent, and renamed properties and enum values get their JSON names
+/// back from the schema.
+#[rstest]
+#[case("array_maxitems", json!([1, 2]))]
+#[case("object_with_oneof", json!({"value": 5}))]
+#[case("object_pattern_properties", json!({"S_a": "x", "I_b": 3}))]
+#[case("object_with_enum_array", json!({"statuses": ["DONE", "NEW"]}))]
+#[case("string_format_temporal", json!({"occurredAt": "1970-01-01T00:00:00Z", "day": "1970-01-01"}))]
+#[case("object_renamed_properties", json!({"first-name": "Ada", "status": "in progress", "home address": {"zip-code": "N1 9GU"}}))]
+fn decode_converted(#[case] stem: &str, #[case] value: Value) {
+    let schema = fixture(stem);
+    let encoded = encode(&schema, value.clone()).unwrap();
+    assert_eq!(decode(&schema, &encoded), value);
+}
+
+/// With the conversion's mapping, renamed properties and enum values get
+/// their JSON names back.
+#[rstest]
+#[case("object_renamed_properties", json!({"first-name": "Ada", "2fa": true, "status": "in progress", "home address": {"zip-code": "N1 9GU"}}))]
+#[case("object_renamed_properties", json!({"first-name": "Grace", "status": "1st"}))]
+#[case("array_maxitems", json!([1, 2]))]
+#[case("object_with_enum_array", json!({"statuses": ["DONE", "NEW"]}))]
+fn decode_mapped(#[case] stem: &str, #[case] value: Value) {
+    let conversion = JsonToAvroConverter::builder()
+        .build()
+        .convert_file(format!("tests/fixtures/jsonschema/{stem}.json"))
+        .unwrap();
+    let encoded = encode(&conversion.schema, value.clone()).unwrap();
+    let decoded = Decoder::new(&conversion.schema)
+        .mapping(&conversion.mapping)
+        .decode(&mut encoded.as_slice())
+        .unwrap();
+    assert_eq!(decoded, value);
+}
+
+/// Non-string `enum` and `const` values come back as themselves, whichever
+/// way they were converted.
+#[rstest]
+fn decode_enum_values(#[values(EnumValues::Typed, EnumValues::Symbols)] strategy: EnumValues) {
+    let options = ConverterOptions {
+        enum_values: strategy,
+        ..ConverterOptions::default()
+    };
+    let schema = JsonToAvroConverter::builder()
+        .options(options)
+        .build()
+        .convert_file("tests/fixtures/jsonschema/enum_non_string.json")
+        .unwrap()
+        .schema;
+    let data = std::fs::read_to_string("tests/fixtures/data/enum_non_string.jsonl").unwrap();
+    for line in data.lines() {
+        let value: Value = serde_json::from_str(line).unwrap();
+        let encoded = encode(&schema, value.clone()).unwrap();
+        assert_eq!(decode(&schema, &encoded), value);
+    }
+    assert!(encode(&schema, json!({"priority": 4, "active": true})).is_err());
+}
+
+/// The mapping tells a required property's explicit `null` from an optional
+/// property that was absent.
+#[test]
+fn decode_mapped_required() {
+    let conversion = JsonToAvroConverter::builder()
+        .build()
+        .convert_file("tests/fixtures/jsonschema/object_required_nullable.json")
+        .unwrap();
+    let mapping = &conversion.mapping;
+    let record = "object_required_nullable.RequiredNullable";
+    assert!(mapping.field(record, "code").unwrap().required);
+    assert!(!mapping.field(record, "note").unwrap().required);
+
+    let value = json!({"name": null, "amount": null, "code": null, "note": null});
+    let encoded = encode(&conversion.schema, value).unwrap();
+    let decoded = Decoder::new(&conversion.schema)
+        .mapping(mapping)
+        .decode(&mut encoded.as_slice())
+        .unwrap();
+    assert_eq!(decoded, json!({"name": null, "amount": null, "code": null}));
+}
+
+#[test]
+fn decode_truncated() {
+    let schema = AvroType::try_from(json!({"type": "array", "items": "string"})).unwrap();
+    let error = Decoder::new(&schema)
+        .decode(&mut &[0x02, 0x06, b'a'][..])
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid data at #/0: unexpected end of data"
+    );
+}
+
+fn sample(len: usize) -> Vec<u8> {
+    // Text with repeats, then bytes from a linear congruential generator
+    let mut state = 1u32;
+    b"the quick brown fox jumps over the lazy dog; "
+        .iter()
+        .copied()
+        .cycle()
+        .take(len / 2)
+        .chain((0..len - len / 2).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        }))
+        .collect()
+}
+
+#[rstest]
+#[case(0)]
+#[case(1)]
+#[case(100)]
+#[case(100_000)]
+fn codec_round_trip(#[case] len: usize) {
+    let block = sample(len);
+    for codec in [Codec::Null, Codec::Deflate, Codec::Snappy] {
+        let compressed = codec.compress(&block);
+        assert_eq!(codec.decompress(&compressed).unwrap(), block, "{codec}");
+    }
+    if len == 100_000 {
+        assert!(deflate::compress(&block).len() < len * 3 / 4);
+        assert!(snappy::compress(&block).len() < len * 3 / 4);
+    }
+}
+
+/// Streams written by zlib, in each kind of block.
+#[rstest]
+#[case(&[0x01, 0x06, 0x00, 0xf9, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64], b"stored")]
+#[case(
+    &[0x4b, 0x4c, 0x84, 0x81, 0x24, 0x10, 0x48, 0x4e, 0x4e, 0x4e, 0x49, 0x49, 0x4d, 0x24, 0x5e,
+      0x30, 0x29, 0x39, 0x25, 0x35, 0x35, 0x25, 0x39, 0x29, 0x11, 0x00],
+    b"aaaaaaaaaabbbbbcccddeaaaaaaaaaabbbbbcccddeaaaaaaaaaabbbbbcccddeabcdeedcba"
+)]
+#[case(
+    &[0x2d, 0x8e, 0x81, 0x11, 0x00, 0x21, 0x0c, 0xc2, 0x56, 0xe9, 0x6a, 0x81, 0xee, 0x3f, 0xc3,
+      0x53, 0xfc, 0x7a, 0x9e, 0x96, 0x42, 0x14, 0xc4, 0xec, 0x18, 0x06, 0x9b, 0x15, 0x11, 0x52,
+      0xce, 0x62, 0xe7, 0x9a, 0x68, 0x3e, 0x55, 0x46, 0x8a, 0xb3, 0x46, 0x89, 0x8d, 0x4b, 0x93,
+      0x36, 0x83, 0x96, 0xa5, 0xe7, 0x8d, 0x27, 0xe7, 0xc8, 0x09, 0x6d, 0x47, 0x65, 0xee, 0x41,
+      0xb8, 0x68, 0x71, 0x31, 0xcf, 0xe4, 0xe6, 0x03, 0xd3, 0x60, 0xf7, 0xfa, 0xa1, 0xcd, 0x33,
+      0x5e, 0xc0, 0x3f, 0x80, 0x8a, 0xfd, 0x83, 0xee, 0xe1, 0x1f, 0x65, 0x3e],
+    b"aaba d caa accadbaabaaaacacaad abaadbacaababcabb ca accabbadaacb a abcaaaaaacbbdbacaaca \
+      bac bcbabdaaaaabaaaadababaabbaaababbdb  aabcbcababacaaacaaadcadaacbcabaaabaabbacbaaaadaac\
+      abaaca acb abcbaaababca"
+)]
+fn deflate_decompress(#[case] stream: &[u8], #[case] expected: &[u8]) {
+    assert_eq!(deflate::decompress(stream).unwrap(), expected);
+}
+
+#[test]
+fn snappy_checksum() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    let mut block = Codec::Snappy.compress(b"abc");
+    *block.last_mut().unwrap() ^= 1;
+    assert_eq!(
+        Codec::Snappy.decompress(&block).unwrap_err().to_string(),
+        "invalid Avro object container file: corrupt snappy block: checksum mismatch"
+    );
+}
+
+/// A block claiming a huge length is rejected once it decompresses past it,
+/// rather than allocating the claimed length up front.
+#[test]
+fn snappy_oversized_length() {
+    let mut block = vec![0xff, 0xff, 0xff, 0xff, 0x7f];
+    block.extend(&snappy::compress(b"abc")[1..]);
+    let err = snappy::decompress(&block).unwrap_err();
+    assert_eq!(err, "decompressed 3 bytes, expected 34359738367");
+    let mut block = vec![0x02];
+    block.extend(&snappy::compress(b"abc")[1..]);
+    assert_eq!(
+        snappy::decompress(&block).unwrap_err(),
+        "decompressed more than the expected 2 bytes"
+    );
+}
+
+/// A container file holds its root schema standalone, and reads back the
+/// instances written to it across several blocks.
+#[rstest]
+#[case(Codec::Null)]
+#[case(Codec::Deflate)]
+#[case(Codec::Snappy)]
+fn container_round_trip(#[case] codec: Codec) {
+    let schema = fixture("object_with_enum_array");
+    let values: Vec<Value> = (0..50)
+        .map(|i| json!({"statuses": if i % 3 == 0 { json!(["NEW"]) } else { json!(["DONE", "NEW"]) }}))
+        .collect();
+    let mut writer = Writer::new(&schema, codec).sync_interval(16);
+    for value in &values {
+        writer.append(value).unwrap();
+    }
+    let bytes = writer.finish();
+    assert_eq!(&bytes[..4], b"Obj\x01");
+
+    let container = Container::read(&bytes).unwrap();
+    assert_eq!(container.codec, codec);
+    assert!(!matches!(container.schema, AvroType::Union(_)));
+    assert_eq!(container.values().unwrap(), values);
+}
+
+/// With the mapping, a required property that may be `null` keeps its
+/// explicit `null` through a container file, while an absent optional one
+/// stays absent.
+#[rstest]
+fn container_required_null(#[values(Codec::Null, Codec::Deflate, Codec::Snappy)] codec: Codec) {
+    let conversion = JsonToAvroConverter::builder()
+        .build()
+        .convert_file("tests/fixtures/jsonschema/object_required_nullable.json")
+        .unwrap();
+    let values = [
+        json!({"name": null, "amount": null, "code": null}),
+        json!({"name": "a", "amount": 2, "code": "x", "note": null, "label": "b"}),
+        json!({"name": null, "amount": "c", "code": true, "note": "d"}),
+    ];
+    let mut writer = Writer::new(&conversion.schema, codec);
+    for value in &values {
+        writer.append(value).unwrap();
+    }
+    let container = Container::read(&writer.finish()).unwrap();
+    assert_eq!(
+        container.mapped_values(&conversion.mapping).unwrap(),
+        [
+            json!({"name": null, "amount": null, "code": null}),
+            json!({"name": "a", "amount": 2, "code": "x", "label": "b"}),
+            json!({"name": null, "amount": "c", "code": true, "note": "d"}),
+        ]
+    );
+}
+
+#[test]
+fn container_errors() {
+    let schema = fixture("object_with_enum_array");
+    let mut writer = Writer::new(&schema, Codec::Deflate);
+    writer.append(&json!({"statuses": ["NEW"]})).unwrap();
+    let bytes = writer.finish();
+
+    let error = |bytes: &[u8]| Container::read(bytes).err().unwrap().to_string();
+    assert!(error(b"PAR1").ends_with("not an Avro object container file"));
+    assert!(error(&bytes[..bytes.len() - 1]).ends_with("block does not end with the sync marker"));
+    assert!(error(&bytes[..bytes.len() - 20]).ends_with("unexpected end of file"));
+}
+
+/// A block can't claim more values than its bytes hold, nor, for values that
+/// take no bytes, more than a reader should collect.
+#[rstest]
+#[case(json!("int"), json!(7), 1, None)]
+#[case(json!("int"), json!(7), 5, Some("block claims 5 values in 1 bytes"))]
+#[case(json!("null"), json!(null), 3, None)]
+#[case(json!("null"), json!(null), 1 << 40, Some("block claims 1099511627776 values in 0 bytes"))]
+fn container_block_count(
+    #[case] schema: Value,
+    #[case] value: Value,
+    #[case] count: u64,
+    #[case] message: Option<&str>,
+) {
+    let schema = AvroType::try_from(schema).unwrap();
+    let mut bytes = Writer::new(&schema, Codec::Null).finish();
+    let sync = bytes[bytes.len() - 16..].to_vec();
+    let data = encode(&schema, value.clone()).unwrap();
+    for n in [count, data.len() as u64] {
+        let mut n = n << 1;
+        while n >= 0x80 {
+            bytes.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        bytes.push(n as u8);
+    }
+    bytes.extend(data);
+    bytes.extend(sync);
+    match message {
+        None => {
+            let values = Container::read(&bytes).unwrap().values().unwrap();
+            assert_eq!(values, vec![value; count as usize]);
+        }
+        Some(message) => {
+            let error = Container::read(&bytes).err().unwrap().to_string();
+            assert!(error.ends_with(message), "{error}");
+        }
+    }
+}
+
+/// Types the root refers to are defined in the header's schema, including
+/// those nested in other top-level types.
+#[test]
+fn container_schema_standalone() {
+    let schema = fixture("mutually_recursive_defs");
+    let bytes = Writer::new(&schema, Codec::Null).finish();
+    let container = Container::read(&bytes).unwrap();
+    assert_eq!(
+        container.schema.full_name().as_deref(),
+        Some("mutually_recursive_defs.Organisation")
+    );
+    validate(&container.schema).unwrap();
+    assert!(container.values().unwrap().is_empty());
+}